@@ -0,0 +1,370 @@
+//! `spacemesh-sdk` command-line tool, to inspect hardware wallets, keys and signatures without
+//! writing a program against the SDK.
+//!
+//! Every command prints a JSON value with `--json`, and the same fields as text otherwise.
+
+mod output;
+
+use {
+    clap::{Parser, Subcommand},
+    serde_json::{json, Value},
+    spacemesh_derivation_path::DerivationPath,
+    spacemesh_remote_wallet::{
+        keystore,
+        locator::Locator,
+        remote_keypair::generate_remote_keypair,
+        remote_wallet::{
+            initialize_wallet_manager, maybe_wallet_manager, DeviceSelection, RemoteWalletError,
+            RemoteWalletInfo,
+        },
+        signer_source::{
+            signer_from_path, SecretPrompt, SignerFromPathConfig, SignerSource, SignerSourceKind,
+        },
+        watch_only::WatchOnlyDescriptor,
+    },
+    spacemesh_signer::{check_derivation_path, OffchainMessage, Pubkey, Signature},
+    spacemesh_transaction::{wallet, MAINNET_HRP},
+    std::{error::Error, process::ExitCode},
+};
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+/// Derivation path of the first account, as used by smapp
+const DEFAULT_PATH: &str = "m/44'/540'/0'/0'/0'";
+/// Name of the key in prompts
+const KEYPAIR_NAME: &str = "signer";
+
+#[derive(Parser, Debug)]
+#[command(name = "spacemesh-sdk", version, about)]
+struct Cli {
+    /// Print JSON instead of text
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// List connected hardware wallets, with their app settings and the errors reading them
+    ListDevices,
+
+    /// Get the pubkey and address at a derivation path of a hardware wallet
+    Pubkey {
+        /// Device locator, e.g. `usb://ledger?serial=0001`
+        #[arg(default_value = "usb://ledger")]
+        locator: String,
+
+        #[arg(long, default_value = DEFAULT_PATH)]
+        path: String,
+
+        /// Show the pubkey on the device for the user to confirm
+        #[arg(long)]
+        confirm: bool,
+
+        /// Show the address on the device for the user to confirm, and check it against the
+        /// address computed from the pubkey
+        #[arg(long, conflicts_with = "confirm")]
+        verify_address: bool,
+
+        /// Human-readable part of the address
+        #[arg(long, default_value = MAINNET_HRP)]
+        hrp: String,
+    },
+
+    /// Derive keys and addresses from a mnemonic, read from the terminal
+    Derive {
+        /// Derivation path, may be repeated. Defaults to the first `--count` accounts.
+        #[arg(long = "path")]
+        paths: Vec<String>,
+
+        /// Number of accounts to derive, at `m/44'/540'/0'/0'/<index>'`
+        #[arg(long, default_value_t = 1, conflicts_with = "paths")]
+        count: u32,
+
+        /// Also read a BIP39 passphrase
+        #[arg(long)]
+        passphrase: bool,
+
+        /// Human-readable part of the addresses
+        #[arg(long, default_value = MAINNET_HRP)]
+        hrp: String,
+    },
+
+    /// Sign an off-chain message, which can't be replayed as a transaction
+    Sign {
+        /// Signer: a hardware wallet (`usb://ledger?key=0/0`), a wallet file, or `prompt://` to
+        /// enter a mnemonic
+        signer: String,
+
+        message: String,
+    },
+
+    /// Verify the signature of an off-chain message
+    Verify {
+        /// Hex-encoded public key
+        pubkey: String,
+
+        message: String,
+
+        /// Hex-encoded signature
+        signature: String,
+    },
+
+    /// Check that a derivation path can be used to derive Spacemesh keys
+    ValidatePath { path: String },
+
+    /// Check a hardware wallet locator and show the device selectors it holds
+    ValidateLocator { locator: String },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli.command) {
+        Ok(value) => {
+            output::print(&value, cli.json);
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            if cli.json {
+                output::print(&json!({ "error": err.to_string() }), true);
+            } else {
+                eprintln!("error: {err}");
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(command: Command) -> Result<Value> {
+    match command {
+        Command::ListDevices => list_devices(),
+        Command::Pubkey {
+            locator,
+            path,
+            confirm,
+            verify_address,
+            hrp,
+        } => pubkey(&locator, &path, confirm, verify_address, &hrp),
+        Command::Derive {
+            paths,
+            count,
+            passphrase,
+            hrp,
+        } => derive(&paths, count, passphrase, &hrp),
+        Command::Sign { signer, message } => sign(&signer, &message),
+        Command::Verify {
+            pubkey,
+            message,
+            signature,
+        } => verify(&pubkey, &message, &signature),
+        Command::ValidatePath { path } => validate_path(&path),
+        Command::ValidateLocator { locator } => validate_locator(&locator),
+    }
+}
+
+fn list_devices() -> Result<Value> {
+    let wallet_manager = initialize_wallet_manager()?;
+    // devices that can't be read are still listed, with the error
+    if let Err(err) = wallet_manager.probe_devices() {
+        if wallet_manager.list_devices().is_empty() {
+            return Err(err.into());
+        }
+    }
+    let devices: Vec<_> = wallet_manager
+        .list_devices()
+        .iter()
+        .map(device_info)
+        .collect();
+    Ok(json!({ "devices": devices }))
+}
+
+fn device_info(info: &RemoteWalletInfo) -> Value {
+    json!({
+        "path": info.get_pretty_path(),
+        "manufacturer": info.manufacturer.to_string(),
+        "model": info.model.map(|model| model.to_string()),
+        "serial": info.serial,
+        "hostDevicePath": info.host_device_path,
+        "app": info.app.as_ref().map(|app| json!({
+            "name": app.name,
+            "version": app.version,
+        })),
+        "settings": info.settings.map(|settings| json!({
+            "blindSigning": settings.enable_blind_signing,
+            "pubkeyDisplay": format!("{:?}", settings.pubkey_display),
+        })),
+        "error": info.error.as_ref().map(ToString::to_string),
+    })
+}
+
+fn pubkey(
+    locator: &str,
+    path: &str,
+    confirm: bool,
+    verify_address: bool,
+    hrp: &str,
+) -> Result<Value> {
+    let locator = Locator::new_from_path(locator)?;
+    let derivation_path = DerivationPath::from_absolute_path_str(path)?;
+    let wallet_manager = maybe_wallet_manager()?.ok_or(RemoteWalletError::NoDeviceFound)?;
+    let keypair = generate_remote_keypair(
+        locator,
+        derivation_path,
+        &wallet_manager,
+        confirm,
+        KEYPAIR_NAME,
+        &DeviceSelection::Interactive,
+    )?;
+    let address = if verify_address {
+        keypair.verify_address(hrp)?
+    } else {
+        wallet::principal(&keypair.pubkey).encode(hrp)?
+    };
+    Ok(json!({
+        "device": keypair.path,
+        "path": format!("{:?}", keypair.derivation_path),
+        "pubkey": keypair.pubkey.to_string(),
+        "address": address,
+        "confirmed": confirm || verify_address,
+    }))
+}
+
+fn derive(paths: &[String], count: u32, passphrase: bool, hrp: &str) -> Result<Value> {
+    let paths = if paths.is_empty() {
+        (0..count)
+            .map(|index| format!("m/44'/540'/0'/0'/{index}'"))
+            .collect()
+    } else {
+        paths.to_vec()
+    };
+    let derivation_paths = paths
+        .iter()
+        .map(|path| DerivationPath::from_absolute_path_str(path))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let prompt = SecretPrompt::Terminal;
+    let mnemonic = prompt.prompt("Seed phrase")?;
+    let passphrase = if passphrase {
+        prompt.prompt("BIP39 passphrase (empty for none)")?
+    } else {
+        String::new()
+    };
+    let seed = keystore::mnemonic_to_seed(&mnemonic, &passphrase)?;
+    Ok(serde_json::to_value(WatchOnlyDescriptor::from_seed(
+        &seed,
+        &derivation_paths,
+        hrp,
+    )?)?)
+}
+
+fn sign(signer: &str, message: &str) -> Result<Value> {
+    let message = OffchainMessage::new(message.as_bytes())?;
+    let source = SignerSource::parse(signer)?;
+    let (pubkey, signature) = match source.kind {
+        // devices can't parse off-chain messages, sign them as such so that blind signing is
+        // checked first
+        SignerSourceKind::Usb(locator) => {
+            let wallet_manager = maybe_wallet_manager()?.ok_or(RemoteWalletError::NoDeviceFound)?;
+            let keypair = generate_remote_keypair(
+                locator,
+                source.derivation_path.unwrap_or_default(),
+                &wallet_manager,
+                false,
+                KEYPAIR_NAME,
+                &DeviceSelection::Interactive,
+            )?;
+            (keypair.pubkey, keypair.sign_offchain_message(&message)?)
+        }
+        _ => {
+            let signer = signer_from_path(
+                signer,
+                KEYPAIR_NAME,
+                &mut None,
+                &SignerFromPathConfig::default(),
+            )?;
+            (signer.try_pubkey()?, message.sign(signer.as_ref())?)
+        }
+    };
+    Ok(json!({
+        "pubkey": pubkey.to_string(),
+        "signature": signature.to_string(),
+    }))
+}
+
+fn verify(pubkey: &str, message: &str, signature: &str) -> Result<Value> {
+    let pubkey: Pubkey = pubkey.parse()?;
+    let signature: Signature = signature.parse()?;
+    let message = OffchainMessage::new(message.as_bytes())?;
+    if !message.verify(&pubkey, &signature) {
+        return Err("signature does not verify".into());
+    }
+    Ok(json!({ "valid": true }))
+}
+
+fn validate_path(path: &str) -> Result<Value> {
+    let derivation_path = DerivationPath::from_absolute_path_str(path)?;
+    check_derivation_path(&derivation_path)?;
+    let index = |index: Option<&_>| index.map(|index| format!("{index}"));
+    Ok(json!({
+        "path": format!("{derivation_path:?}"),
+        "account": index(derivation_path.account()),
+        "change": index(derivation_path.change()),
+        "address": index(derivation_path.address()),
+        "query": derivation_path.get_query(),
+    }))
+}
+
+fn validate_locator(locator: &str) -> Result<Value> {
+    let locator = Locator::new_from_path(locator)?;
+    Ok(json!({
+        "locator": locator.to_string(),
+        "manufacturer": locator.manufacturer.to_string(),
+        "pubkey": locator.pubkey.map(|pubkey| pubkey.to_string()),
+        "serial": locator.serial,
+        "model": locator.model.map(|model| model.to_string()),
+        "hostDevicePath": locator.host_device_path,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, clap::CommandFactory};
+
+    #[test]
+    fn test_cli() {
+        Cli::command().debug_assert();
+
+        let cli = Cli::parse_from(["spacemesh-sdk", "validate-path", "m/44'/540'/1'", "--json"]);
+        assert!(cli.json);
+        assert_eq!(
+            run(cli.command).unwrap(),
+            json!({
+                "path": "m/44'/540'/1'",
+                "account": "1'",
+                "change": null,
+                "address": null,
+                "query": "?key=1'",
+            })
+        );
+        assert!(validate_path("m/44'/501'/0'").is_err());
+    }
+
+    #[test]
+    fn test_verify() {
+        let message = OffchainMessage::new(b"message").unwrap();
+        let keypair =
+            spacemesh_signer::SoftwareKeypair::from_seed(&[1u8; 64], DerivationPath::default())
+                .unwrap();
+        let pubkey = spacemesh_signer::Signer::pubkey(&keypair).to_string();
+        let signature = message.sign(&keypair).unwrap().to_string();
+
+        assert_eq!(
+            verify(&pubkey, "message", &signature).unwrap(),
+            json!({ "valid": true })
+        );
+        assert!(verify(&pubkey, "messagE", &signature).is_err());
+        assert!(verify("00", "message", &signature).is_err());
+    }
+}
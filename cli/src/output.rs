@@ -0,0 +1,80 @@
+use serde_json::Value;
+
+/// Print `value` to stdout, as pretty JSON if `json` is true and as indented `key: value` lines
+/// otherwise. Null fields are left out of the text output.
+pub fn print(value: &Value, json: bool) {
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(value).unwrap_or_default()
+        );
+    } else {
+        let mut out = String::new();
+        write_text(&mut out, value, 0);
+        print!("{out}");
+    }
+}
+
+fn write_text(out: &mut String, value: &Value, indent: usize) {
+    let pad = "  ".repeat(indent);
+    match value {
+        Value::Object(fields) => {
+            for (key, value) in fields {
+                match value {
+                    Value::Null => {}
+                    Value::Object(_) | Value::Array(_) => {
+                        out.push_str(&format!("{pad}{key}:\n"));
+                        write_text(out, value, indent + 1);
+                    }
+                    _ => out.push_str(&format!("{pad}{key}: {}\n", scalar(value))),
+                }
+            }
+        }
+        Value::Array(items) => {
+            if items.is_empty() {
+                out.push_str(&format!("{pad}(none)\n"));
+            }
+            for (i, item) in items.iter().enumerate() {
+                match item {
+                    Value::Object(_) | Value::Array(_) => {
+                        out.push_str(&format!("{pad}[{i}]\n"));
+                        write_text(out, item, indent + 1);
+                    }
+                    _ => out.push_str(&format!("{pad}{}\n", scalar(item))),
+                }
+            }
+        }
+        _ => out.push_str(&format!("{pad}{}\n", scalar(value))),
+    }
+}
+
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, serde_json::json};
+
+    #[test]
+    fn test_write_text() {
+        let mut out = String::new();
+        write_text(
+            &mut out,
+            &json!({
+                "devices": [{ "path": "usb://ledger", "error": null, "settings": { "blindSigning": true } }],
+                "count": 1,
+                "empty": [],
+            }),
+            0,
+        );
+        assert_eq!(
+            out,
+            "devices:\n  [0]\n    path: usb://ledger\n    settings:\n      blindSigning: true\n\
+             count: 1\nempty:\n  (none)\n"
+        );
+    }
+}
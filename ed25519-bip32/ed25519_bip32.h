@@ -19,3 +19,24 @@ uint16_t derive_c(const uint8_t *seed,
                   size_t seedlen,
                   const char *derivation_path_ptr,
                   uint8_t *result);
+
+/**
+ * sign_offchain_message_c signs the message_len bytes at message as an off-chain message, which
+ * can't be replayed as a transaction, with the 64-byte keypair returned by derive_c. It writes
+ * the signature to result, which must be at least 64 bytes long. It returns a status code, with
+ * a return value of zero indicating success.
+ */
+uint16_t sign_offchain_message_c(const uint8_t *keypair,
+                                 const uint8_t *message,
+                                 size_t message_len,
+                                 uint8_t *result);
+
+/**
+ * verify_offchain_message_c checks that the 64-byte signature is a signature of the
+ * message_len bytes at message, as an off-chain message, by the 32-byte pubkey. It returns zero
+ * if the signature is valid and a nonzero value otherwise.
+ */
+uint16_t verify_offchain_message_c(const uint8_t *pubkey,
+                                   const uint8_t *message,
+                                   size_t message_len,
+                                   const uint8_t *signature);
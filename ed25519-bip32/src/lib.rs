@@ -2,7 +2,8 @@ extern crate ed25519_dalek_bip32;
 extern crate wasm_bindgen;
 
 use std::ffi::{c_char, CStr};
-use ed25519_dalek_bip32::{ed25519_dalek::{Keypair, KEYPAIR_LENGTH, SECRET_KEY_LENGTH}, DerivationPath, ExtendedSecretKey};
+use ed25519_dalek_bip32::{ed25519_dalek::{Keypair, Signer, KEYPAIR_LENGTH, PUBLIC_KEY_LENGTH, SECRET_KEY_LENGTH, SIGNATURE_LENGTH}, DerivationPath, ExtendedSecretKey};
+use spacemesh_signer::{OffchainMessage, Pubkey, Signature};
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
@@ -29,6 +30,7 @@ pub fn derive_key(
 /// it adds error handling in order to be friendlier to the FFI caller: in case of an error, it
 /// prints the error and returns a nonzero value.
 #[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub extern "C" fn derive_c(
     seed: *const u8,
     seedlen: usize,
@@ -95,3 +97,110 @@ fn _derive_c(
     let extended_public_key = extended.public_key();
     Ok(Keypair{secret: extended.secret_key, public: extended_public_key})
 }
+
+#[wasm_bindgen]
+/// sign_offchain_message signs message as an off-chain message, which can't be replayed as a
+/// transaction, with the 64-byte keypair returned by derive_key. it returns the 64-byte signature.
+pub fn sign_offchain_message(
+    keypair: &[u8],
+    message: &[u8],
+) -> Result<Box<[u8]>, JsError> {
+    let signature = _sign_offchain_message(keypair, message).map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(Box::new(signature))
+}
+
+#[wasm_bindgen]
+/// verify_offchain_message returns whether signature is a signature of the off-chain message
+/// message by the 32-byte public key pubkey.
+pub fn verify_offchain_message(
+    pubkey: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> bool {
+    _verify_offchain_message(pubkey, message, signature)
+}
+
+/// Borrow the len bytes at ptr. A NULL ptr is only accepted for an empty slice.
+fn slice_from_c<'a>(ptr: *const u8, len: usize) -> Option<&'a [u8]> {
+    match (ptr.is_null(), len) {
+        (true, 0) => Some(&[]),
+        (true, _) => None,
+        (false, _) => Some(unsafe { std::slice::from_raw_parts(ptr, len) }),
+    }
+}
+
+/// sign_offchain_message_c signs the message_len bytes at message as an off-chain message, which
+/// can't be replayed as a transaction, with the 64-byte keypair returned by derive_c. It writes
+/// the signature to result, which must be at least 64 bytes long. It returns a status code, with
+/// a return value of zero indicating success.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn sign_offchain_message_c(
+    keypair: *const u8,
+    message: *const u8,
+    message_len: usize,
+    result: *mut u8,
+) -> u16 {
+    let (Some(keypair), Some(message), false) = (
+        slice_from_c(keypair, KEYPAIR_LENGTH),
+        slice_from_c(message, message_len),
+        result.is_null(),
+    ) else {
+        eprintln!("unexpected NULL pointer");
+        return 1;
+    };
+    match _sign_offchain_message(keypair, message) {
+        Ok(signature) => {
+            let result_slice = unsafe { std::slice::from_raw_parts_mut(result, SIGNATURE_LENGTH) };
+            result_slice.copy_from_slice(&signature);
+            0
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            1
+        }
+    }
+}
+
+/// verify_offchain_message_c checks that the 64-byte signature is a signature of the
+/// message_len bytes at message, as an off-chain message, by the 32-byte pubkey. It returns zero
+/// if the signature is valid and a nonzero value otherwise.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn verify_offchain_message_c(
+    pubkey: *const u8,
+    message: *const u8,
+    message_len: usize,
+    signature: *const u8,
+) -> u16 {
+    match (
+        slice_from_c(pubkey, PUBLIC_KEY_LENGTH),
+        slice_from_c(message, message_len),
+        slice_from_c(signature, SIGNATURE_LENGTH),
+    ) {
+        (Some(pubkey), Some(message), Some(signature))
+            if _verify_offchain_message(pubkey, message, signature) => 0,
+        _ => 1,
+    }
+}
+
+fn _sign_offchain_message(
+    keypair: &[u8],
+    message: &[u8],
+) -> Result<[u8; SIGNATURE_LENGTH], Box<dyn std::error::Error>> {
+    let keypair = Keypair::from_bytes(keypair)
+        .map_err(|e| format!("invalid keypair: {}", e))?;
+    let message = OffchainMessage::new(message)?;
+    Ok(keypair.sign(&message.signing_body()).to_bytes())
+}
+
+fn _verify_offchain_message(pubkey: &[u8], message: &[u8], signature: &[u8]) -> bool {
+    let (Ok(pubkey), Ok(signature), Ok(message)) = (
+        Pubkey::try_from(pubkey),
+        Signature::try_from(signature),
+        OffchainMessage::new(message),
+    ) else {
+        return false;
+    };
+    message.verify(&pubkey, &signature)
+}
@@ -7,26 +7,114 @@
 #include <stdlib.h>
 
 
-#define DEPRECATED_GET_APP_CONFIGURATION 1
+/**
+ * Status code returned by verify_address_on_ledger if the address displayed by the device doesn't
+ * match the one computed from its pubkey
+ */
+#define ADDRESS_MISMATCH 2
+
+/**
+ * Status code returned by verify_address_on_ledger if the device displayed an address for
+ * another network, i.e. with another hrp
+ */
+#define NETWORK_MISMATCH 3
+
+typedef enum PubkeyDisplayMode {
+  Short,
+  Long,
+} PubkeyDisplayMode;
 
-#define DEPRECATED_GET_PUBKEY 2
+/**
+ * Kind of a `RemoteWalletEvent`, as passed across FFI
+ */
+typedef enum RemoteWalletEventKind {
+  AwaitingConfirmation = 0,
+  Approved = 1,
+  Rejected = 2,
+  ChunkProgress = 3,
+} RemoteWalletEventKind;
 
-#define DEPRECATED_SIGN_MESSAGE 3
+/**
+ * Spacemesh app settings, as configured by the user on the device
+ */
+typedef struct LedgerSettings {
+  bool enable_blind_signing;
+  enum PubkeyDisplayMode pubkey_display;
+} LedgerSettings;
 
-#define GET_APP_CONFIGURATION 4
+/**
+ * C callback receiving user-interaction events. `device` is a NUL-terminated description of the
+ * device for `AwaitingConfirmation` events and NULL otherwise; it is only valid for the duration
+ * of the call. `chunks_sent` and `chunks_total` are only set for `ChunkProgress` events.
+ */
+typedef void (*RemoteWalletEventCallback)(enum RemoteWalletEventKind kind,
+                                          const char *device,
+                                          size_t chunks_sent,
+                                          size_t chunks_total,
+                                          void *user_data);
 
-#define GET_PUBKEY 5
 
-#define SIGN_MESSAGE 6
 
 /**
  * read_pubkey_from_ledger reads a pubkey from the ledger device specified by path and
- * derivation_path. If path is empty, the first ledger device found will be used. If confirm_key
+ * derivation_path. If path is empty, the first ledger device found will be used. path may select
+ * a device by serial number, model or host path, e.g. usb://ledger?serial=0001. If confirm_key
  * is true, it will prompt the user to confirm the key on the device. It writes the pubkey bytes
  * to result, which must be at least 32 bytes long. It returns a status code, with a return value
- * of zero indicating success.
+ * of zero indicating success. If the device returned an error, the status code is the status word
+ * it returned (e.g. 0x5515 if the device is locked); any other error returns 1.
  */
 uint16_t read_pubkey_from_ledger(const char *path,
                                  const char *derivation_path_ptr,
                                  bool confirm_key,
                                  uint8_t *result);
+
+/**
+ * read_settings_from_ledger reads the Spacemesh app settings, such as whether blind signing is
+ * enabled, from the ledger device specified by path. If path is empty, the first ledger device
+ * found will be used. It writes the settings to result. It returns a status code, with a return
+ * value of zero indicating success. If the device returned an error, the status code is the status
+ * word it returned; any other error returns 1.
+ */
+uint16_t read_settings_from_ledger(const char *path, struct LedgerSettings *result);
+
+/**
+ * set_event_callback registers a callback that receives user-interaction events, such as the
+ * device waiting for the user's approval, instead of having them printed to stdout. user_data is
+ * passed back to the callback unchanged. Passing a NULL callback silences all events.
+ */
+void set_event_callback(RemoteWalletEventCallback callback, void *user_data);
+
+/**
+ * sign_offchain_message_with_ledger signs the message_len bytes at message as an off-chain
+ * message, which can't be replayed as a transaction, with the key at derivation_path on the
+ * ledger device specified by path. If path is empty, the first ledger device found will be used.
+ * Blind signing must be enabled in the Spacemesh app. It writes the signature to result, which
+ * must be at least 64 bytes long. It returns a status code, with a return value of zero indicating
+ * success. If the device returned an error, the status code is the status word it returned; any
+ * other error returns 1.
+ */
+uint16_t sign_offchain_message_with_ledger(const char *path,
+                                           const char *derivation_path_ptr,
+                                           const uint8_t *message,
+                                           size_t message_len,
+                                           uint8_t *result);
+
+/**
+ * verify_address_on_ledger asks the ledger device specified by path to display the address of
+ * the wallet account controlled by the key at derivation_path, for the user to compare with the
+ * address shown by the app, and checks it against the address computed from the key, encoded with
+ * hrp (e.g. "sm"). If path is empty, the first ledger device found will be used. It writes the
+ * address, NUL-terminated, to result, which must be at least result_cap bytes long; 64 bytes are
+ * enough for addresses with short hrps. It returns a status code, with a return value of zero
+ * indicating success. If the device displayed an address for another hrp, it returns
+ * NETWORK_MISMATCH; if the addresses otherwise don't match, it returns ADDRESS_MISMATCH. In both
+ * cases the address must not be used. If the device returned an error, the status code is the
+ * status word it returned (e.g. 0x5501 if the user rejected the address); any other error
+ * returns 1.
+ */
+uint16_t verify_address_on_ledger(const char *path,
+                                  const char *derivation_path_ptr,
+                                  const char *hrp,
+                                  char *result,
+                                  size_t result_cap);
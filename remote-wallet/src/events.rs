@@ -0,0 +1,218 @@
+use {
+    console::Emoji,
+    parking_lot::RwLock,
+    std::{
+        ffi::{c_char, c_void, CString},
+        sync::Arc,
+    },
+};
+
+static CHECK_MARK: Emoji = Emoji("✅ ", "");
+
+static EVENT_SINK: RwLock<Option<Arc<dyn RemoteWalletEventSink>>> = RwLock::new(None);
+
+/// User-interaction event emitted while talking to a remote wallet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemoteWalletEvent {
+    /// The device is waiting for the user to approve or reject a request
+    AwaitingConfirmation {
+        device_name: String,
+        pretty_path: String,
+    },
+    /// The user approved the request on the device
+    Approved,
+    /// The user rejected the request on the device
+    Rejected,
+    /// `sent` out of `total` chunks of a payload have been delivered to the device
+    ChunkProgress { sent: usize, total: usize },
+}
+
+/// Receiver for `RemoteWalletEvent`s, allowing hosts to render the device UX themselves
+pub trait RemoteWalletEventSink: Send + Sync {
+    fn on_event(&self, event: &RemoteWalletEvent);
+}
+
+/// Sink that prints confirmation prompts to stdout. This is the default sink.
+#[derive(Debug, Default)]
+pub struct ConsoleEventSink;
+
+impl RemoteWalletEventSink for ConsoleEventSink {
+    fn on_event(&self, event: &RemoteWalletEvent) {
+        match event {
+            RemoteWalletEvent::AwaitingConfirmation {
+                device_name,
+                pretty_path,
+            } => println!("Waiting for your approval on {device_name} {pretty_path}"),
+            RemoteWalletEvent::Approved => println!("{CHECK_MARK}Approved"),
+            RemoteWalletEvent::Rejected | RemoteWalletEvent::ChunkProgress { .. } => {}
+        }
+    }
+}
+
+/// Sink that discards all events
+#[derive(Debug, Default)]
+pub struct NullEventSink;
+
+impl RemoteWalletEventSink for NullEventSink {
+    fn on_event(&self, _event: &RemoteWalletEvent) {}
+}
+
+/// Kind of a `RemoteWalletEvent`, as passed across FFI
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemoteWalletEventKind {
+    AwaitingConfirmation = 0,
+    Approved = 1,
+    Rejected = 2,
+    ChunkProgress = 3,
+}
+
+/// C callback receiving user-interaction events. `device` is a NUL-terminated description of the
+/// device for `AwaitingConfirmation` events and NULL otherwise; it is only valid for the duration
+/// of the call. `chunks_sent` and `chunks_total` are only set for `ChunkProgress` events.
+pub type RemoteWalletEventCallback = Option<
+    extern "C" fn(
+        kind: RemoteWalletEventKind,
+        device: *const c_char,
+        chunks_sent: usize,
+        chunks_total: usize,
+        user_data: *mut c_void,
+    ),
+>;
+
+/// Sink forwarding events to a C callback registered by the host
+pub struct CallbackEventSink {
+    callback: extern "C" fn(RemoteWalletEventKind, *const c_char, usize, usize, *mut c_void),
+    user_data: *mut c_void,
+}
+
+// The host is responsible for `user_data` being usable from whichever thread talks to the device.
+unsafe impl Send for CallbackEventSink {}
+unsafe impl Sync for CallbackEventSink {}
+
+impl CallbackEventSink {
+    pub fn new(
+        callback: extern "C" fn(RemoteWalletEventKind, *const c_char, usize, usize, *mut c_void),
+        user_data: *mut c_void,
+    ) -> Self {
+        Self {
+            callback,
+            user_data,
+        }
+    }
+}
+
+impl RemoteWalletEventSink for CallbackEventSink {
+    fn on_event(&self, event: &RemoteWalletEvent) {
+        let (kind, device, sent, total) = match event {
+            RemoteWalletEvent::AwaitingConfirmation {
+                device_name,
+                pretty_path,
+            } => (
+                RemoteWalletEventKind::AwaitingConfirmation,
+                CString::new(format!("{device_name} {pretty_path}")).ok(),
+                0,
+                0,
+            ),
+            RemoteWalletEvent::Approved => (RemoteWalletEventKind::Approved, None, 0, 0),
+            RemoteWalletEvent::Rejected => (RemoteWalletEventKind::Rejected, None, 0, 0),
+            RemoteWalletEvent::ChunkProgress { sent, total } => {
+                (RemoteWalletEventKind::ChunkProgress, None, *sent, *total)
+            }
+        };
+        let device_ptr = device
+            .as_ref()
+            .map_or(std::ptr::null(), |device| device.as_ptr());
+        (self.callback)(kind, device_ptr, sent, total, self.user_data);
+    }
+}
+
+/// Install the sink that receives all subsequent remote wallet events
+pub fn set_event_sink(sink: Arc<dyn RemoteWalletEventSink>) {
+    *EVENT_SINK.write() = Some(sink);
+}
+
+/// Get the installed event sink, defaulting to `ConsoleEventSink`
+pub fn event_sink() -> Arc<dyn RemoteWalletEventSink> {
+    EVENT_SINK
+        .read()
+        .clone()
+        .unwrap_or_else(|| Arc::new(ConsoleEventSink))
+}
+
+pub(crate) fn emit(event: RemoteWalletEvent) {
+    event_sink().on_event(&event);
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, parking_lot::Mutex};
+
+    struct RecordingSink(Mutex<Vec<RemoteWalletEvent>>);
+
+    impl RemoteWalletEventSink for RecordingSink {
+        fn on_event(&self, event: &RemoteWalletEvent) {
+            self.0.lock().push(event.clone());
+        }
+    }
+
+    type CallbackRecord = (RemoteWalletEventKind, Option<String>, usize, usize);
+
+    static CALLBACK_EVENTS: Mutex<Vec<CallbackRecord>> = Mutex::new(Vec::new());
+
+    extern "C" fn record_callback(
+        kind: RemoteWalletEventKind,
+        device: *const c_char,
+        chunks_sent: usize,
+        chunks_total: usize,
+        _user_data: *mut c_void,
+    ) {
+        let device = (!device.is_null()).then(|| {
+            unsafe { std::ffi::CStr::from_ptr(device) }
+                .to_string_lossy()
+                .to_string()
+        });
+        CALLBACK_EVENTS
+            .lock()
+            .push((kind, device, chunks_sent, chunks_total));
+    }
+
+    #[test]
+    fn test_event_sink() {
+        let sink = Arc::new(RecordingSink(Mutex::new(vec![])));
+        set_event_sink(sink.clone());
+        emit(RemoteWalletEvent::ChunkProgress { sent: 1, total: 2 });
+        emit(RemoteWalletEvent::Approved);
+        set_event_sink(Arc::new(NullEventSink));
+        emit(RemoteWalletEvent::Rejected);
+        assert_eq!(
+            *sink.0.lock(),
+            vec![
+                RemoteWalletEvent::ChunkProgress { sent: 1, total: 2 },
+                RemoteWalletEvent::Approved,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_callback_event_sink() {
+        let sink = CallbackEventSink::new(record_callback, std::ptr::null_mut());
+        sink.on_event(&RemoteWalletEvent::AwaitingConfirmation {
+            device_name: "Ledger hardware wallet".to_string(),
+            pretty_path: "usb://ledger".to_string(),
+        });
+        sink.on_event(&RemoteWalletEvent::ChunkProgress { sent: 2, total: 3 });
+        assert_eq!(
+            *CALLBACK_EVENTS.lock(),
+            vec![
+                (
+                    RemoteWalletEventKind::AwaitingConfirmation,
+                    Some("Ledger hardware wallet usb://ledger".to_string()),
+                    0,
+                    0
+                ),
+                (RemoteWalletEventKind::ChunkProgress, None, 2, 3),
+            ]
+        );
+    }
+}
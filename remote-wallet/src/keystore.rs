@@ -0,0 +1,713 @@
+use {
+    crate::locator::{Locator, LocatorError},
+    aes_gcm::{
+        aead::{rand_core::RngCore, Aead, OsRng},
+        Aes256Gcm, KeyInit, Nonce,
+    },
+    bip39::Mnemonic,
+    hmac::Hmac,
+    serde::{Deserialize, Serialize},
+    sha2::Sha512,
+    spacemesh_derivation_path::{DerivationPath, DerivationPathError},
+    spacemesh_signer::{Pubkey, Signer, SignerError, SoftwareKeypair},
+    std::{fmt, fs, path::Path},
+    thiserror::Error,
+};
+
+/// Cipher, key derivation function and hash used by smapp and smcli wallet files
+pub const CIPHER_AES_GCM: &str = "AES-GCM";
+pub const KDF_PBKDF2: &str = "PBKDF2";
+pub const HASH_SHA512: &str = "SHA-512";
+
+/// Keystore version written by this crate. Files written by smapp and smcli have no version and
+/// are version 1. Version 2 adds Ledger-backed accounts and requires the public key of every
+/// account.
+/// cbindgen:ignore
+pub const KEYSTORE_VERSION: u32 = 2;
+/// Key derivation iterations of new wallet files, as used by smapp
+/// cbindgen:ignore
+pub const DEFAULT_KDF_ITERATIONS: u32 = 120_000;
+
+/// Length of the AES-256 key derived from the password
+const KEY_LEN: usize = 32;
+/// Length of the AES-GCM nonce
+const IV_LEN: usize = 12;
+/// Length of the key derivation salt of new wallet files
+const SALT_LEN: usize = 16;
+
+/// Keystore error
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum KeystoreError {
+    #[error("failed to read keystore: {0}")]
+    Io(String),
+
+    #[error("invalid keystore: {0}")]
+    Format(String),
+
+    #[error("unsupported keystore cipher `{0}`")]
+    UnsupportedCipher(String),
+
+    #[error("unsupported keystore key derivation `{0}`")]
+    UnsupportedKdf(String),
+
+    #[error("wrong keystore password")]
+    WrongPassword,
+
+    #[error("invalid mnemonic: {0}")]
+    InvalidMnemonic(String),
+
+    #[error("keystore has no accounts")]
+    NoAccounts,
+
+    #[error("unsupported keystore version {0}, at most {KEYSTORE_VERSION} supported")]
+    UnsupportedVersion(u32),
+
+    #[error("failed to encrypt keystore")]
+    Encryption,
+
+    #[error(transparent)]
+    DerivationPathError(#[from] DerivationPathError),
+
+    #[error(transparent)]
+    LocatorError(#[from] LocatorError),
+
+    #[error(transparent)]
+    SignerError(#[from] SignerError),
+}
+
+impl From<std::io::Error> for KeystoreError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for KeystoreError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Format(err.to_string())
+    }
+}
+
+/// Encrypted JSON wallet file, as written by smapp and smcli. Fields this crate doesn't know about
+/// are kept in order, along with the indentation of the file, so that writing back a file that
+/// was read reproduces it byte for byte, except for the fields that were changed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct WalletFile {
+    pub meta: WalletMeta,
+    pub crypto: EncryptedSecrets,
+    #[serde(skip)]
+    layout: JsonLayout,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct WalletMeta {
+    pub display_name: String,
+    pub created: String,
+    /// Keystore version, absent in version 1 files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Layout of the JSON text of a wallet file
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct JsonLayout {
+    /// Indentation of pretty-printed files, `None` for compact ones
+    indent: Option<String>,
+    trailing_newline: bool,
+}
+
+impl JsonLayout {
+    fn detect(json: &str) -> Self {
+        let indent = json
+            .trim_start()
+            .strip_prefix('{')
+            .and_then(|rest| {
+                rest.strip_prefix('\n')
+                    .or_else(|| rest.strip_prefix("\r\n"))
+            })
+            .map(|rest| {
+                rest.chars()
+                    .take_while(|c| *c == ' ' || *c == '\t')
+                    .collect::<String>()
+            })
+            .filter(|indent| !indent.is_empty());
+        Self {
+            indent,
+            trailing_newline: json.ends_with('\n'),
+        }
+    }
+}
+
+/// Wallet secrets encrypted with a key derived from the wallet password
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EncryptedSecrets {
+    pub cipher: String,
+    /// Hex-encoded ciphertext, followed by the authentication tag
+    #[serde(rename = "cipherText")]
+    pub cipher_text: String,
+    #[serde(rename = "cipherParams")]
+    pub cipher_params: CipherParams,
+    pub kdf: String,
+    pub kdfparams: KdfParams,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CipherParams {
+    /// Hex-encoded nonce
+    pub iv: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct KdfParams {
+    /// Derived key length. smapp writes it in bits, smcli in bytes.
+    pub dklen: u32,
+    pub hash: String,
+    /// Hex-encoded salt
+    pub salt: String,
+    pub iterations: u32,
+}
+
+/// Decrypted content of a wallet file
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct WalletSecrets {
+    pub mnemonic: String,
+    #[serde(default)]
+    pub accounts: Vec<Account>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl fmt::Debug for WalletSecrets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WalletSecrets")
+            .field("accounts", &self.accounts)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Account derived from the wallet mnemonic, or held by a hardware wallet
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Account {
+    pub display_name: String,
+    pub created: String,
+    /// Absolute derivation path, e.g. `m/44'/540'/0'/0'/0'`
+    pub path: String,
+    /// Hex-encoded public key
+    pub public_key: String,
+    /// Hex-encoded secret key followed by the public key, absent for hardware wallet accounts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_key: Option<String>,
+    /// Locator of the hardware wallet holding the key, e.g. `usb://ledger?serial=0001`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locator: Option<String>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl fmt::Debug for Account {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Account")
+            .field("display_name", &self.display_name)
+            .field("created", &self.created)
+            .field("path", &self.path)
+            .field("public_key", &self.public_key)
+            .field("locator", &self.locator)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Account {
+    /// Account of the key derived from `seed` at `derivation_path`
+    pub fn from_seed(
+        display_name: &str,
+        created: &str,
+        seed: &[u8],
+        derivation_path: DerivationPath,
+    ) -> Result<Self, KeystoreError> {
+        let path = format!("{derivation_path:?}");
+        let keypair = SoftwareKeypair::from_seed(seed, derivation_path)?;
+        Ok(Self {
+            display_name: display_name.to_string(),
+            created: created.to_string(),
+            path,
+            public_key: hex::encode(keypair.pubkey()),
+            secret_key: Some(hex::encode(keypair.to_bytes())),
+            locator: None,
+            extra: serde_json::Map::new(),
+        })
+    }
+
+    /// Account of the key `pubkey` at `derivation_path` on the hardware wallet at `locator`
+    pub fn remote(
+        display_name: &str,
+        created: &str,
+        locator: &Locator,
+        derivation_path: &DerivationPath,
+        pubkey: &Pubkey,
+    ) -> Self {
+        Self {
+            display_name: display_name.to_string(),
+            created: created.to_string(),
+            path: format!("{derivation_path:?}"),
+            public_key: hex::encode(pubkey),
+            secret_key: None,
+            locator: Some(locator.to_string()),
+            extra: serde_json::Map::new(),
+        }
+    }
+
+    pub fn derivation_path(&self) -> Result<DerivationPath, KeystoreError> {
+        Ok(DerivationPath::from_absolute_path_str(&self.path)?)
+    }
+
+    pub fn pubkey(&self) -> Result<Pubkey, KeystoreError> {
+        Pubkey::try_from(decode_hex("publicKey", &self.public_key)?.as_slice())
+            .map_err(|_| KeystoreError::Format(format!("invalid public key `{}`", self.public_key)))
+    }
+
+    /// Whether the key is held by a hardware wallet rather than derived from the mnemonic
+    pub fn is_remote(&self) -> bool {
+        self.locator.is_some()
+    }
+
+    /// Locator of the hardware wallet holding the key, if any
+    pub fn locator(&self) -> Result<Option<Locator>, KeystoreError> {
+        Ok(self
+            .locator
+            .as_deref()
+            .map(Locator::new_from_path)
+            .transpose()?)
+    }
+}
+
+impl WalletFile {
+    /// Encrypt `secrets` with `password` into a new wallet file of the current version
+    pub fn new(
+        mut meta: WalletMeta,
+        secrets: &WalletSecrets,
+        password: &str,
+    ) -> Result<Self, KeystoreError> {
+        meta.version = Some(KEYSTORE_VERSION);
+        let kdfparams = KdfParams {
+            dklen: (KEY_LEN * 8) as u32,
+            hash: HASH_SHA512.to_string(),
+            salt: String::new(),
+            iterations: DEFAULT_KDF_ITERATIONS,
+        };
+        Ok(Self {
+            meta,
+            crypto: EncryptedSecrets::encrypt(&serde_json::to_vec(secrets)?, password, kdfparams)?,
+            layout: JsonLayout::default(),
+        })
+    }
+
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self, KeystoreError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, KeystoreError> {
+        let mut wallet: Self = serde_json::from_str(json)?;
+        wallet.layout = JsonLayout::detect(json);
+        Ok(wallet)
+    }
+
+    /// Serialize with the layout of the file this was read from, compact for new files
+    pub fn to_json(&self) -> Result<String, KeystoreError> {
+        let mut json = match &self.layout.indent {
+            Some(indent) => {
+                let mut out = vec![];
+                let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+                let mut serializer = serde_json::Serializer::with_formatter(&mut out, formatter);
+                self.serialize(&mut serializer)?;
+                String::from_utf8(out).map_err(|e| KeystoreError::Format(e.to_string()))?
+            }
+            None => serde_json::to_string(self)?,
+        };
+        if self.layout.trailing_newline {
+            json.push('\n');
+        }
+        Ok(json)
+    }
+
+    /// Write to `path`, through a temporary file renamed over it, so that an interrupted write
+    /// doesn't leave a truncated wallet behind
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<(), KeystoreError> {
+        let path = path.as_ref();
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        fs::write(&tmp, self.to_json()?)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    pub fn version(&self) -> u32 {
+        self.meta.version.unwrap_or(1)
+    }
+
+    /// Decrypt the wallet secrets with `password`. They are returned as stored, see `migrate` to
+    /// upgrade older files.
+    pub fn decrypt(&self, password: &str) -> Result<WalletSecrets, KeystoreError> {
+        let plaintext = self.crypto.decrypt(password)?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    /// Replace the wallet secrets, after checking `password`. They are upgraded to the current
+    /// version first, and the file along with them.
+    pub fn update_secrets(
+        &mut self,
+        mut secrets: WalletSecrets,
+        password: &str,
+    ) -> Result<(), KeystoreError> {
+        self.crypto.decrypt(password)?;
+        migrate_secrets(&mut secrets, self.version())?;
+        self.crypto = EncryptedSecrets::encrypt(
+            &serde_json::to_vec(&secrets)?,
+            password,
+            self.crypto.kdfparams.clone(),
+        )?;
+        self.meta.version = Some(KEYSTORE_VERSION);
+        Ok(())
+    }
+
+    /// Upgrade the file to the current version. Returns whether it was changed; files of the
+    /// current version are left untouched.
+    pub fn migrate(&mut self, password: &str) -> Result<bool, KeystoreError> {
+        match self.version() {
+            KEYSTORE_VERSION => Ok(false),
+            version if version > KEYSTORE_VERSION => {
+                Err(KeystoreError::UnsupportedVersion(version))
+            }
+            _ => {
+                let secrets = self.decrypt(password)?;
+                self.update_secrets(secrets, password)?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Re-encrypt the secrets with `new_password`, with a fresh salt and the same key derivation
+    /// parameters. The secrets themselves are kept as they are.
+    pub fn change_password(
+        &mut self,
+        old_password: &str,
+        new_password: &str,
+    ) -> Result<(), KeystoreError> {
+        let plaintext = self.crypto.decrypt(old_password)?;
+        self.crypto =
+            EncryptedSecrets::encrypt(&plaintext, new_password, self.crypto.kdfparams.clone())?;
+        Ok(())
+    }
+}
+
+impl EncryptedSecrets {
+    /// Encrypt `plaintext` with the key derived from `password` with `kdfparams`, whose salt is
+    /// replaced by a random one. The nonce is random as well.
+    pub fn encrypt(
+        plaintext: &[u8],
+        password: &str,
+        kdfparams: KdfParams,
+    ) -> Result<Self, KeystoreError> {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let mut iv = [0u8; IV_LEN];
+        OsRng.fill_bytes(&mut iv);
+        let kdfparams = KdfParams {
+            salt: hex::encode(salt),
+            ..kdfparams
+        };
+        let key = kdfparams.derive_key(KDF_PBKDF2, password)?;
+        let cipher_text = Aes256Gcm::new(&key.into())
+            .encrypt(Nonce::from_slice(&iv), plaintext)
+            .map_err(|_| KeystoreError::Encryption)?;
+        Ok(Self {
+            cipher: CIPHER_AES_GCM.to_string(),
+            cipher_text: hex::encode(cipher_text),
+            cipher_params: CipherParams {
+                iv: hex::encode(iv),
+            },
+            kdf: KDF_PBKDF2.to_string(),
+            kdfparams,
+        })
+    }
+
+    pub fn decrypt(&self, password: &str) -> Result<Vec<u8>, KeystoreError> {
+        if self.cipher != CIPHER_AES_GCM {
+            return Err(KeystoreError::UnsupportedCipher(self.cipher.clone()));
+        }
+        let key = self.kdfparams.derive_key(&self.kdf, password)?;
+        let iv = decode_hex("iv", &self.cipher_params.iv)?;
+        if iv.len() != IV_LEN {
+            return Err(KeystoreError::Format(format!(
+                "iv must be {IV_LEN} bytes, got {}",
+                iv.len()
+            )));
+        }
+        let cipher_text = decode_hex("cipherText", &self.cipher_text)?;
+        Aes256Gcm::new(&key.into())
+            .decrypt(Nonce::from_slice(&iv), cipher_text.as_slice())
+            .map_err(|_| KeystoreError::WrongPassword)
+    }
+}
+
+impl KdfParams {
+    /// Derive the encryption key from `password`
+    pub fn derive_key(&self, kdf: &str, password: &str) -> Result<[u8; KEY_LEN], KeystoreError> {
+        if kdf != KDF_PBKDF2 || self.hash != HASH_SHA512 {
+            return Err(KeystoreError::UnsupportedKdf(format!(
+                "{kdf} {}",
+                self.hash
+            )));
+        }
+        if self.dklen as usize != KEY_LEN && self.dklen as usize != KEY_LEN * 8 {
+            return Err(KeystoreError::UnsupportedKdf(format!(
+                "derived key length {}",
+                self.dklen
+            )));
+        }
+        if self.iterations == 0 {
+            return Err(KeystoreError::Format("zero kdf iterations".to_string()));
+        }
+        let salt = decode_hex("salt", &self.salt)?;
+        let mut key = [0u8; KEY_LEN];
+        pbkdf2::pbkdf2::<Hmac<Sha512>>(password.as_bytes(), &salt, self.iterations, &mut key);
+        Ok(key)
+    }
+}
+
+impl WalletSecrets {
+    /// BIP39 seed of the wallet mnemonic. Spacemesh wallets don't use a BIP39 passphrase.
+    pub fn seed(&self) -> Result<[u8; 64], KeystoreError> {
+        mnemonic_to_seed(&self.mnemonic, "")
+    }
+
+    /// Derivation path of the first account derived from the mnemonic, used when none is
+    /// requested explicitly
+    pub fn default_derivation_path(&self) -> Result<DerivationPath, KeystoreError> {
+        self.accounts
+            .iter()
+            .find(|account| !account.is_remote())
+            .ok_or(KeystoreError::NoAccounts)?
+            .derivation_path()
+    }
+}
+
+/// Upgrade of the decrypted secrets from one keystore version to the next
+type Migration = fn(&mut WalletSecrets) -> Result<(), KeystoreError>;
+
+/// `MIGRATIONS[i]` upgrades secrets from version `i + 1` to version `i + 2`
+const MIGRATIONS: [Migration; KEYSTORE_VERSION as usize - 1] = [migrate_v1_to_v2];
+
+/// Upgrade `secrets` from `version` to `KEYSTORE_VERSION`
+fn migrate_secrets(secrets: &mut WalletSecrets, version: u32) -> Result<(), KeystoreError> {
+    if version == 0 || version > KEYSTORE_VERSION {
+        return Err(KeystoreError::UnsupportedVersion(version));
+    }
+    for migration in &MIGRATIONS[version as usize - 1..] {
+        migration(secrets)?;
+    }
+    Ok(())
+}
+
+/// Fill in the public keys of mnemonic accounts that don't have one
+fn migrate_v1_to_v2(secrets: &mut WalletSecrets) -> Result<(), KeystoreError> {
+    if secrets
+        .accounts
+        .iter()
+        .all(|account| !account.public_key.is_empty())
+    {
+        return Ok(());
+    }
+    let seed = secrets.seed()?;
+    for account in &mut secrets.accounts {
+        if account.public_key.is_empty() && !account.is_remote() {
+            let keypair = SoftwareKeypair::from_seed(&seed, account.derivation_path()?)?;
+            account.public_key = hex::encode(keypair.pubkey());
+        }
+    }
+    Ok(())
+}
+
+/// BIP39 seed of `mnemonic` with `passphrase`
+pub fn mnemonic_to_seed(mnemonic: &str, passphrase: &str) -> Result<[u8; 64], KeystoreError> {
+    let mnemonic = Mnemonic::parse_normalized(mnemonic)
+        .map_err(|err| KeystoreError::InvalidMnemonic(err.to_string()))?;
+    Ok(mnemonic.to_seed_normalized(passphrase))
+}
+
+fn decode_hex(field: &str, value: &str) -> Result<Vec<u8>, KeystoreError> {
+    hex::decode(value).map_err(|err| KeystoreError::Format(format!("{field}: {err}")))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon \
+                                       abandon abandon abandon abandon about";
+
+    /// Encrypt `secrets` the way smapp does, as a version 1 file with few iterations to keep
+    /// tests fast
+    pub(crate) fn encrypt_wallet(secrets: &serde_json::Value, password: &str) -> WalletFile {
+        let kdfparams = KdfParams {
+            dklen: 256,
+            hash: HASH_SHA512.to_string(),
+            salt: String::new(),
+            iterations: 1000,
+        };
+        WalletFile {
+            meta: WalletMeta {
+                display_name: "Test wallet".to_string(),
+                created: "2024-01-01T00:00:00.000Z".to_string(),
+                version: None,
+                extra: serde_json::Map::new(),
+            },
+            crypto: EncryptedSecrets::encrypt(secrets.to_string().as_bytes(), password, kdfparams)
+                .unwrap(),
+            layout: JsonLayout::default(),
+        }
+    }
+
+    pub(crate) fn test_secrets() -> serde_json::Value {
+        serde_json::json!({
+            "mnemonic": MNEMONIC,
+            "accounts": [{
+                "displayName": "Main account",
+                "created": "2024-01-01T00:00:00.000Z",
+                "path": "m/44'/540'/0'/0'/0'",
+                "publicKey": "",
+                "secretKey": "",
+            }],
+            "contacts": [],
+        })
+    }
+
+    #[test]
+    fn test_decrypt() {
+        let wallet = encrypt_wallet(&test_secrets(), "password");
+        let json = serde_json::to_string(&wallet).unwrap();
+        let wallet = WalletFile::from_json(&json).unwrap();
+
+        let secrets = wallet.decrypt("password").unwrap();
+        assert_eq!(secrets.mnemonic, MNEMONIC);
+        assert_eq!(secrets.accounts[0].display_name, "Main account");
+        assert_eq!(
+            secrets.default_derivation_path().unwrap(),
+            DerivationPath::from_absolute_path_str("m/44'/540'/0'/0'/0'").unwrap()
+        );
+        assert!(secrets.extra.contains_key("contacts"));
+
+        assert_eq!(wallet.decrypt("wrong"), Err(KeystoreError::WrongPassword));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let wallet = encrypt_wallet(&test_secrets(), "password");
+        let crypto = serde_json::to_string(&wallet.crypto).unwrap();
+        let compact = format!(
+            r#"{{"meta":{{"displayName":"Test wallet","created":"2024-01-01T00:00:00.000Z","netId":1,"remoteApi":"","type":"remote-api"}},"crypto":{crypto}}}"#
+        );
+        assert_eq!(
+            WalletFile::from_json(&compact).unwrap().to_json().unwrap(),
+            compact
+        );
+
+        let pretty = serde_json::to_string_pretty(
+            &serde_json::from_str::<serde_json::Value>(&compact).unwrap(),
+        )
+        .unwrap()
+            + "\n";
+        let wallet = WalletFile::from_json(&pretty).unwrap();
+        assert_eq!(wallet.to_json().unwrap(), pretty);
+        assert_eq!(
+            wallet.meta.extra.keys().collect::<Vec<_>>(),
+            vec!["netId", "remoteApi", "type"]
+        );
+
+        let dir = std::env::temp_dir().join(format!("keystore-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("wallet.json");
+        wallet.write(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), pretty);
+        assert_eq!(WalletFile::read(&path).unwrap(), wallet);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_change_password() {
+        let mut wallet = encrypt_wallet(&test_secrets(), "password");
+        let kdfparams = wallet.crypto.kdfparams.clone();
+        let plaintext = wallet.crypto.decrypt("password").unwrap();
+
+        assert_eq!(
+            wallet.change_password("wrong", "new password"),
+            Err(KeystoreError::WrongPassword)
+        );
+        wallet.change_password("password", "new password").unwrap();
+        assert_eq!(
+            wallet.decrypt("password"),
+            Err(KeystoreError::WrongPassword)
+        );
+        assert_eq!(wallet.crypto.decrypt("new password").unwrap(), plaintext);
+        assert_eq!(wallet.crypto.kdfparams.iterations, kdfparams.iterations);
+        assert_ne!(wallet.crypto.kdfparams.salt, kdfparams.salt);
+        assert_eq!(wallet.version(), 1);
+    }
+
+    #[test]
+    fn test_migrate() {
+        let mut wallet = encrypt_wallet(&test_secrets(), "password");
+        assert_eq!(wallet.version(), 1);
+        assert_eq!(wallet.migrate("wrong"), Err(KeystoreError::WrongPassword));
+        assert!(wallet.migrate("password").unwrap());
+        assert_eq!(wallet.version(), KEYSTORE_VERSION);
+        assert!(!wallet.migrate("password").unwrap());
+
+        let secrets = wallet.decrypt("password").unwrap();
+        let seed = mnemonic_to_seed(MNEMONIC, "").unwrap();
+        let keypair =
+            SoftwareKeypair::from_seed(&seed, secrets.default_derivation_path().unwrap()).unwrap();
+        assert_eq!(secrets.accounts[0].pubkey().unwrap(), keypair.pubkey());
+        assert!(secrets.extra.contains_key("contacts"));
+
+        wallet.meta.version = Some(KEYSTORE_VERSION + 1);
+        assert_eq!(
+            wallet.migrate("password"),
+            Err(KeystoreError::UnsupportedVersion(KEYSTORE_VERSION + 1))
+        );
+    }
+
+    #[test]
+    fn test_new_wallet() {
+        let seed = mnemonic_to_seed(MNEMONIC, "").unwrap();
+        let path = DerivationPath::new_bip44(Some(0), Some(0));
+        let account = Account::from_seed("Main", "2024-01-01", &seed, path.clone()).unwrap();
+        let locator = Locator::new_from_path("usb://ledger?serial=0001").unwrap();
+        let pubkey = Pubkey::new_unique();
+        let ledger = Account::remote("Ledger", "2024-01-01", &locator, &path, &pubkey);
+        let secrets = WalletSecrets {
+            mnemonic: MNEMONIC.to_string(),
+            accounts: vec![ledger, account],
+            extra: serde_json::Map::new(),
+        };
+
+        let wallet = WalletFile::new(WalletMeta::default(), &secrets, "password").unwrap();
+        assert_eq!(wallet.version(), KEYSTORE_VERSION);
+        assert_eq!(wallet.crypto.kdfparams.iterations, DEFAULT_KDF_ITERATIONS);
+        let wallet = WalletFile::from_json(&wallet.to_json().unwrap()).unwrap();
+
+        let decrypted = wallet.decrypt("password").unwrap();
+        assert_eq!(decrypted, secrets);
+        assert_eq!(decrypted.default_derivation_path().unwrap(), path);
+        assert_eq!(decrypted.accounts[0].locator().unwrap(), Some(locator));
+        assert_eq!(decrypted.accounts[0].pubkey().unwrap(), pubkey);
+        assert_eq!(decrypted.accounts[0].secret_key, None);
+        assert_eq!(decrypted.accounts[1].locator().unwrap(), None);
+        assert_eq!(
+            decrypted.accounts[1].pubkey().unwrap(),
+            SoftwareKeypair::from_seed(&seed, path).unwrap().pubkey()
+        );
+    }
+}
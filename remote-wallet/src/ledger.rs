@@ -1,56 +1,52 @@
+//! Driver for Ledger devices running the Spacemesh app.
+//!
+//! The Spacemesh app is built from the Solana Ledger app and keeps its APDU protocol, specified in
+//! `doc/api.md` of <https://github.com/LedgerHQ/app-solana>: commands use CLA `0xe0`, derivation
+//! paths are sent as a count byte followed by big-endian indexes, and messages longer than one
+//! APDU are signed in chunks flagged with `P2_EXTEND` and `P2_MORE`. The app has no instruction
+//! returning an address, so addresses are shown to the user by confirming a public key.
+
+#[cfg(feature = "hidapi")]
+use {
+    crate::{
+        events::{emit, RemoteWalletEvent},
+        ledger_error::LedgerError,
+        locator::DeviceModel,
+        remote_wallet::{is_valid_hid_device, DeviceApp, RemoteWallet},
+    },
+    log::*,
+    parking_lot::{Mutex, ReentrantMutex, ReentrantMutexGuard},
+    spacemesh_signer::Signature,
+    std::{any::Any, cmp::min, convert::TryFrom},
+};
 use {
-    crate::remote_wallet::{
-        RemoteWallet, RemoteWalletError, RemoteWalletInfo, RemoteWalletManager,
+    crate::{
+        ledger_model::LedgerModel,
+        locator::Manufacturer,
+        remote_wallet::{
+            DeviceSelection, RemoteWalletDriver, RemoteWalletError, RemoteWalletInfo,
+            RemoteWalletManager,
+        },
     },
-    console::Emoji,
-    dialoguer::{theme::ColorfulTheme, Select},
+    parking_lot::RwLock,
     semver::Version as FirmwareVersion,
     spacemesh_derivation_path::DerivationPath,
+    spacemesh_signer::Pubkey,
     std::{fmt, sync::Arc},
 };
-#[cfg(feature = "hidapi")]
-use {
-    crate::{ledger_error::LedgerError, locator::Manufacturer},
-    log::*,
-    num_traits::FromPrimitive,
-    solana_sdk::{pubkey::Pubkey, signature::Signature},
-    std::{cmp::min, convert::TryFrom},
-};
-
-static CHECK_MARK: Emoji = Emoji("✅ ", "");
-
-const DEPRECATE_VERSION_BEFORE: FirmwareVersion = FirmwareVersion::new(0, 2, 0);
 
 const APDU_TAG: u8 = 0x05;
 const APDU_CLA: u8 = 0xe0;
 const APDU_PAYLOAD_HEADER_LEN: usize = 7;
-const DEPRECATED_APDU_PAYLOAD_HEADER_LEN: usize = 8;
 const P1_NON_CONFIRM: u8 = 0x00;
 const P1_CONFIRM: u8 = 0x01;
 const P2_EXTEND: u8 = 0x01;
 const P2_MORE: u8 = 0x02;
-const MAX_CHUNK_SIZE: usize = 255;
 
-const APDU_SUCCESS_CODE: usize = 0x9000;
+const APDU_SUCCESS_CODE: u16 = 0x9000;
 
 /// Ledger vendor ID
 const LEDGER_VID: u16 = 0x2c97;
-/// Ledger product IDs
-const LEDGER_NANO_S_PIDS: [u16; 33] = [
-    0x0001, 0x1000, 0x1001, 0x1002, 0x1003, 0x1004, 0x1005, 0x1006, 0x1007, 0x1008, 0x1009, 0x100a,
-    0x100b, 0x100c, 0x100d, 0x100e, 0x100f, 0x1010, 0x1011, 0x1012, 0x1013, 0x1014, 0x1015, 0x1016,
-    0x1017, 0x1018, 0x1019, 0x101a, 0x101b, 0x101c, 0x101d, 0x101e, 0x101f,
-];
-const LEDGER_NANO_X_PIDS: [u16; 33] = [
-    0x0004, 0x4000, 0x4001, 0x4002, 0x4003, 0x4004, 0x4005, 0x4006, 0x4007, 0x4008, 0x4009, 0x400a,
-    0x400b, 0x400c, 0x400d, 0x400e, 0x400f, 0x4010, 0x4011, 0x4012, 0x4013, 0x4014, 0x4015, 0x4016,
-    0x4017, 0x4018, 0x4019, 0x401a, 0x401b, 0x401c, 0x401d, 0x401e, 0x401f,
-];
-const LEDGER_NANO_S_PLUS_PIDS: [u16; 33] = [
-    0x0005, 0x5000, 0x5001, 0x5002, 0x5003, 0x5004, 0x5005, 0x5006, 0x5007, 0x5008, 0x5009, 0x500a,
-    0x500b, 0x500c, 0x500d, 0x500e, 0x500f, 0x5010, 0x5011, 0x5012, 0x5013, 0x5014, 0x5015, 0x5016,
-    0x5017, 0x5018, 0x5019, 0x501a, 0x501b, 0x501c, 0x501d, 0x501e, 0x501f,
-];
 const LEDGER_TRANSPORT_HEADER_LEN: usize = 5;
 
 const HID_PACKET_SIZE: usize = 64 + HID_PREFIX_ZERO;
@@ -60,38 +56,145 @@ const HID_PREFIX_ZERO: usize = 1;
 #[cfg(not(windows))]
 const HID_PREFIX_ZERO: usize = 0;
 
+/// Spacemesh Ledger app instruction set, see the module documentation
+/// cbindgen:ignore
 mod commands {
-    pub const DEPRECATED_GET_APP_CONFIGURATION: u8 = 0x01;
-    pub const DEPRECATED_GET_PUBKEY: u8 = 0x02;
-    pub const DEPRECATED_SIGN_MESSAGE: u8 = 0x03;
+    /// Returns the app settings flags followed by the app version (major, minor, patch)
     pub const GET_APP_CONFIGURATION: u8 = 0x04;
+    /// Returns the 32-byte public key for a derivation path. With `P1_CONFIRM`, the device
+    /// displays the address of the key and waits for the user to approve it.
     pub const GET_PUBKEY: u8 = 0x05;
+    /// Signs a message with the keys at a list of derivation paths, sent in one or more chunks
     pub const SIGN_MESSAGE: u8 = 0x06;
 }
 
-enum ConfigurationVersion {
-    Deprecated(Vec<u8>),
-    Current(Vec<u8>),
+/// Ledger dashboard commands, handled by the device OS whichever app is running
+/// cbindgen:ignore
+mod dashboard {
+    pub const CLA: u8 = 0xb0;
+    /// Returns the name and version of the running app, or of the dashboard
+    pub const GET_APP_AND_VERSION: u8 = 0x01;
+    /// Quits the running app. Sent with `CLA`.
+    pub const QUIT_APP: u8 = 0xa7;
+    /// Opens the app whose name is given as data. Sent with `APDU_CLA`, from the dashboard only.
+    pub const OPEN_APP: u8 = 0xd8;
 }
 
-#[derive(Debug)]
+/// Name the Spacemesh app reports to the dashboard
+pub const SPACEMESH_APP_NAME: &str = "Spacemesh";
+/// Name the dashboard reports when no app is running
+const DASHBOARD_APP_NAME: &str = "BOLOS";
+/// Oldest Spacemesh app version speaking the protocol implemented here
+pub const MIN_SUPPORTED_APP_VERSION: FirmwareVersion = FirmwareVersion::new(0, 1, 0);
+
+const APP_CONFIGURATION_LEN: usize = 5;
+const PUBKEY_LEN: usize = 32;
+const SIGNATURE_LEN: usize = 64;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PubkeyDisplayMode {
     Short,
     Long,
 }
 
-#[derive(Debug)]
+/// Spacemesh app settings, as configured by the user on the device
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct LedgerSettings {
     pub enable_blind_signing: bool,
     pub pubkey_display: PubkeyDisplayMode,
 }
 
-/// Ledger Wallet device
+/// App running on a Ledger device, as reported by the dashboard
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunningApp {
+    pub name: String,
+    pub version: String,
+}
+
+impl RunningApp {
+    pub fn is_spacemesh(&self) -> bool {
+        self.name == SPACEMESH_APP_NAME
+    }
+
+    pub fn is_dashboard(&self) -> bool {
+        self.name == DASHBOARD_APP_NAME
+    }
+
+    /// Whether this is a Spacemesh app older than `MIN_SUPPORTED_APP_VERSION`. Versions that
+    /// can't be parsed are assumed to need updating.
+    pub fn needs_update(&self) -> bool {
+        self.is_spacemesh()
+            && FirmwareVersion::parse(&self.version)
+                .map_or(true, |version| version < MIN_SUPPORTED_APP_VERSION)
+    }
+}
+
+/// Parse the response to `dashboard::GET_APP_AND_VERSION`: a format byte, then the
+/// length-prefixed app name and version, followed by flags we don't use
+fn parse_app_and_version(response: &[u8]) -> Result<RunningApp, RemoteWalletError> {
+    const ERR: RemoteWalletError = RemoteWalletError::Protocol("Unexpected app and version format");
+    let (&format, rest) = response.split_first().ok_or(ERR)?;
+    if format != 1 {
+        return Err(ERR);
+    }
+    let mut rest = rest;
+    let mut next_field = || -> Result<String, RemoteWalletError> {
+        let (&len, tail) = rest.split_first().ok_or(ERR)?;
+        if tail.len() < len as usize {
+            return Err(ERR);
+        }
+        let (field, tail) = tail.split_at(len as usize);
+        rest = tail;
+        String::from_utf8(field.to_vec()).map_err(|_| ERR)
+    };
+    let name = next_field()?;
+    let version = next_field()?;
+    Ok(RunningApp { name, version })
+}
+
+/// Values read from a device, cached after they are first fetched
+#[derive(Debug, Default)]
+struct ProbedValues {
+    app: Option<RunningApp>,
+    version: Option<FirmwareVersion>,
+    settings: Option<LedgerSettings>,
+    base_pubkey: Option<Pubkey>,
+    /// Why the base pubkey could not be read, if it couldn't
+    base_pubkey_error: Option<RemoteWalletError>,
+}
+
+/// Channel HID reports are exchanged with a Ledger device over
+#[cfg(feature = "hidapi")]
+pub trait LedgerTransport: Send {
+    /// Write one report, returning the number of bytes written
+    fn write(&self, data: &[u8]) -> Result<usize, RemoteWalletError>;
+
+    /// Read one report into `buf`, returning the number of bytes read
+    fn read(&self, buf: &mut [u8]) -> Result<usize, RemoteWalletError>;
+}
+
+#[cfg(feature = "hidapi")]
+impl LedgerTransport for hidapi::HidDevice {
+    fn write(&self, data: &[u8]) -> Result<usize, RemoteWalletError> {
+        Ok(hidapi::HidDevice::write(self, data)?)
+    }
+
+    fn read(&self, buf: &mut [u8]) -> Result<usize, RemoteWalletError> {
+        Ok(hidapi::HidDevice::read(self, buf)?)
+    }
+}
+
+/// Ledger Wallet device. It can be shared between threads: each operation runs in an exclusive
+/// session, and operations from other threads wait for it to end.
 pub struct LedgerWallet {
     #[cfg(feature = "hidapi")]
-    pub device: hidapi::HidDevice,
-    pub pretty_path: String,
-    pub version: FirmwareVersion,
+    pub device: Mutex<Box<dyn LedgerTransport>>,
+    #[cfg(feature = "hidapi")]
+    session: ReentrantMutex<()>,
+    pub model: LedgerModel,
+    probed: RwLock<ProbedValues>,
 }
 
 impl fmt::Debug for LedgerWallet {
@@ -102,14 +205,37 @@ impl fmt::Debug for LedgerWallet {
 
 #[cfg(feature = "hidapi")]
 impl LedgerWallet {
-    pub fn new(device: hidapi::HidDevice) -> Self {
+    pub fn new(device: impl LedgerTransport + 'static) -> Self {
         Self {
-            device,
-            pretty_path: String::default(),
-            version: FirmwareVersion::new(0, 0, 0),
+            device: Mutex::new(Box::new(device)),
+            session: ReentrantMutex::new(()),
+            model: LedgerModel::default(),
+            probed: RwLock::default(),
         }
     }
 
+    /// Read the device metadata reported over HID, without talking to the device
+    fn read_device(
+        &mut self,
+        dev_info: &hidapi::DeviceInfo,
+    ) -> Result<RemoteWalletInfo, RemoteWalletError> {
+        let manufacturer = dev_info
+            .manufacturer_string()
+            .and_then(|s| Manufacturer::try_from(s).ok())
+            .unwrap_or_default();
+        let model = LedgerModel::from_product_id(dev_info.product_id());
+        self.model = model.unwrap_or_default();
+        let serial = dev_info.serial_number().unwrap_or("Unknown").to_string();
+        let host_device_path = dev_info.path().to_string_lossy().to_string();
+        Ok(self.fill_info(RemoteWalletInfo {
+            model: model.map(DeviceModel::Ledger),
+            manufacturer,
+            serial,
+            host_device_path,
+            ..RemoteWalletInfo::default()
+        }))
+    }
+
     // Transport Protocol:
     //		* Communication Channel Id		(2 bytes big endian )
     //		* Command Tag				(1 byte)
@@ -122,29 +248,26 @@ impl LedgerWallet {
     //		* APDU_INS				(1 byte)
     //		* APDU_P1				(1 byte)
     //		* APDU_P2				(1 byte)
-    //		* APDU_LENGTH 	        (1 byte (2 bytes DEPRECATED))
+    //		* APDU_LENGTH 	        (1 byte)
     //		* APDU_Payload				(Variable)
     //
     fn write(
         &self,
+        cla: u8,
         command: u8,
         p1: u8,
         p2: u8,
         data: &[u8],
-        outdated_app: bool,
     ) -> Result<(), RemoteWalletError> {
         let data_len = data.len();
         let mut offset = 0;
         let mut sequence_number = 0;
         let mut hid_chunk = [0_u8; HID_PACKET_SIZE];
+        let device = self.device.lock();
 
         while sequence_number == 0 || offset < data_len {
             let header = if sequence_number == 0 {
-                if outdated_app {
-                    LEDGER_TRANSPORT_HEADER_LEN + DEPRECATED_APDU_PAYLOAD_HEADER_LEN
-                } else {
-                    LEDGER_TRANSPORT_HEADER_LEN + APDU_PAYLOAD_HEADER_LEN
-                }
+                LEDGER_TRANSPORT_HEADER_LEN + APDU_PAYLOAD_HEADER_LEN
             } else {
                 LEDGER_TRANSPORT_HEADER_LEN
             };
@@ -160,36 +283,22 @@ impl LedgerWallet {
                 ]);
 
                 if sequence_number == 0 {
-                    if outdated_app {
-                        let data_len = data.len() + 6;
-                        chunk[5..13].copy_from_slice(&[
-                            (data_len >> 8) as u8,
-                            (data_len & 0xff) as u8,
-                            APDU_CLA,
-                            command,
-                            p1,
-                            p2,
-                            (data.len() >> 8) as u8,
-                            data.len() as u8,
-                        ]);
-                    } else {
-                        let data_len = data.len() + 5;
-                        chunk[5..12].copy_from_slice(&[
-                            (data_len >> 8) as u8,
-                            (data_len & 0xff) as u8,
-                            APDU_CLA,
-                            command,
-                            p1,
-                            p2,
-                            data.len() as u8,
-                        ]);
-                    }
+                    let data_len = data.len() + 5;
+                    chunk[5..12].copy_from_slice(&[
+                        (data_len >> 8) as u8,
+                        (data_len & 0xff) as u8,
+                        cla,
+                        command,
+                        p1,
+                        p2,
+                        data.len() as u8,
+                    ]);
                 }
 
                 chunk[header..header + size].copy_from_slice(&data[offset..offset + size]);
             }
             trace!("Ledger write {:?}", &hid_chunk[..]);
-            let n = self.device.write(&hid_chunk[..])?;
+            let n = device.write(&hid_chunk[..])?;
             if n < size + header {
                 return Err(RemoteWalletError::Protocol("Write data size mismatch"));
             }
@@ -217,11 +326,12 @@ impl LedgerWallet {
     fn read(&self) -> Result<Vec<u8>, RemoteWalletError> {
         let mut message_size = 0;
         let mut message = Vec::new();
+        let device = self.device.lock();
 
         // terminate the loop if `sequence_number` reaches its max_value and report error
         for chunk_index in 0..=0xffff {
             let mut chunk: [u8; HID_PACKET_SIZE] = [0; HID_PACKET_SIZE];
-            let chunk_size = self.device.read(&mut chunk)?;
+            let chunk_size = device.read(&mut chunk)?;
             trace!("Ledger read {:?}", &chunk[..]);
             if chunk_size < LEDGER_TRANSPORT_HEADER_LEN
                 || chunk[0] != 0x01
@@ -253,8 +363,7 @@ impl LedgerWallet {
         if message.len() < 2 {
             return Err(RemoteWalletError::Protocol("No status word"));
         }
-        let status =
-            (message[message.len() - 2] as usize) << 8 | (message[message.len() - 1] as usize);
+        let status = u16::from_be_bytes([message[message.len() - 2], message[message.len() - 1]]);
         trace!("Read status {:x}", status);
         Self::parse_status(status)?;
         let new_len = message.len() - 2;
@@ -262,140 +371,232 @@ impl LedgerWallet {
         Ok(message)
     }
 
-    fn _send_apdu(
+    /// Start an exclusive session with the device, lasting until the returned guard is dropped.
+    /// Every operation opens its own session, so this is only needed to keep other threads from
+    /// interleaving their requests between several operations.
+    pub fn session(&self) -> ReentrantMutexGuard<'_, ()> {
+        self.session.lock()
+    }
+
+    fn send_apdu(
         &self,
         command: u8,
         p1: u8,
         p2: u8,
         data: &[u8],
-        outdated_app: bool,
     ) -> Result<Vec<u8>, RemoteWalletError> {
-        self.write(command, p1, p2, data, outdated_app)?;
-        if p1 == P1_CONFIRM && is_last_part(p2) {
-            println!(
-                "Waiting for your approval on {} {}",
-                self.name(),
-                self.pretty_path
-            );
-            let result = self.read()?;
-            println!("{}Approved", CHECK_MARK);
-            Ok(result)
-        } else {
-            self.read()
-        }
+        let confirm = p1 == P1_CONFIRM && is_last_part(p2);
+        self.exchange(APDU_CLA, command, p1, p2, data, confirm)
     }
 
-    fn send_apdu(
+    /// Send a command and read its response. If `confirm` is true, the device waits for the user
+    /// to approve the command.
+    fn exchange(
         &self,
+        cla: u8,
         command: u8,
         p1: u8,
         p2: u8,
         data: &[u8],
+        confirm: bool,
     ) -> Result<Vec<u8>, RemoteWalletError> {
-        self._send_apdu(command, p1, p2, data, self.outdated_app())
+        // the response must be read before anyone else writes to the device
+        let _session = self.session();
+        self.write(cla, command, p1, p2, data)?;
+        if confirm {
+            emit(RemoteWalletEvent::AwaitingConfirmation {
+                device_name: self.name().to_string(),
+                pretty_path: self.pretty_path(),
+            });
+            let result = self.read();
+            match &result {
+                Ok(_) => emit(RemoteWalletEvent::Approved),
+                Err(RemoteWalletError::LedgerError(
+                    LedgerError::UserCancel | LedgerError::UserRefused,
+                )) => emit(RemoteWalletEvent::Rejected),
+                Err(_) => {}
+            }
+            result
+        } else {
+            self.read()
+        }
     }
 
-    fn get_firmware_version(&self) -> Result<FirmwareVersion, RemoteWalletError> {
-        self.get_configuration_vector().map(|config| match config {
-            ConfigurationVersion::Current(config) => {
-                FirmwareVersion::new(config[2].into(), config[3].into(), config[4].into())
-            }
-            ConfigurationVersion::Deprecated(config) => {
-                FirmwareVersion::new(config[1].into(), config[2].into(), config[3].into())
-            }
-        })
+    /// Get the name and version of the app running on the device, or of the dashboard if no app
+    /// is running
+    pub fn get_running_app(&self) -> Result<RunningApp, RemoteWalletError> {
+        let response = self.exchange(
+            dashboard::CLA,
+            dashboard::GET_APP_AND_VERSION,
+            0,
+            0,
+            &[],
+            false,
+        )?;
+        let app = parse_app_and_version(&response)?;
+        self.probed.write().app = Some(app.clone());
+        Ok(app)
     }
 
-    pub fn get_settings(&self) -> Result<LedgerSettings, RemoteWalletError> {
-        self.get_configuration_vector().map(|config| match config {
-            ConfigurationVersion::Current(config) => {
-                let enable_blind_signing = config[0] != 0;
-                let pubkey_display = if config[1] == 0 {
-                    PubkeyDisplayMode::Long
-                } else {
-                    PubkeyDisplayMode::Short
-                };
-                LedgerSettings {
-                    enable_blind_signing,
-                    pubkey_display,
-                }
-            }
-            ConfigurationVersion::Deprecated(_) => LedgerSettings {
-                enable_blind_signing: false,
-                pubkey_display: PubkeyDisplayMode::Short,
-            },
-        })
+    /// Ask the dashboard to open the app called `name`, e.g. `SPACEMESH_APP_NAME`. The user may
+    /// have to approve it on the device. The device reconnects once the app starts, so devices
+    /// must be updated before the app can be used.
+    pub fn open_app(&self, name: &str) -> Result<(), RemoteWalletError> {
+        match self.exchange(APDU_CLA, dashboard::OPEN_APP, 0, 0, name.as_bytes(), true) {
+            Ok(_) => Ok(()),
+            Err(RemoteWalletError::LedgerError(LedgerError::SdkInvalidCounter)) => Err(
+                RemoteWalletError::InvalidInput(format!("app {name:?} is not installed")),
+            ),
+            Err(err) => Err(err),
+        }
     }
 
-    fn get_configuration_vector(&self) -> Result<ConfigurationVersion, RemoteWalletError> {
-        if let Ok(config) = self._send_apdu(commands::GET_APP_CONFIGURATION, 0, 0, &[], false) {
-            if config.len() != 5 {
-                return Err(RemoteWalletError::Protocol("Version packet size mismatch"));
-            }
-            Ok(ConfigurationVersion::Current(config))
-        } else {
-            let config =
-                self._send_apdu(commands::DEPRECATED_GET_APP_CONFIGURATION, 0, 0, &[], true)?;
-            if config.len() != 4 {
-                return Err(RemoteWalletError::Protocol("Version packet size mismatch"));
-            }
-            Ok(ConfigurationVersion::Deprecated(config))
+    /// Quit the running app and return to the dashboard. The device reconnects once the
+    /// dashboard starts, so devices must be updated afterwards.
+    pub fn quit_app(&self) -> Result<(), RemoteWalletError> {
+        self.exchange(dashboard::CLA, dashboard::QUIT_APP, 0, 0, &[], false)
+            .map(|_| ())
+    }
+
+    /// Get the Spacemesh app version, reading it from the device on first use
+    pub fn get_firmware_version(&self) -> Result<FirmwareVersion, RemoteWalletError> {
+        if let Some(version) = self.probed.read().version.clone() {
+            return Ok(version);
         }
+        self.get_configuration().map(|(version, _)| version)
     }
 
-    fn outdated_app(&self) -> bool {
-        self.version < DEPRECATE_VERSION_BEFORE
+    /// Get the Spacemesh app settings. They are always read from the device, since the user can
+    /// change them at any time.
+    pub fn get_settings(&self) -> Result<LedgerSettings, RemoteWalletError> {
+        self.get_configuration().map(|(_, settings)| settings)
     }
 
-    fn parse_status(status: usize) -> Result<(), RemoteWalletError> {
+    fn get_configuration(&self) -> Result<(FirmwareVersion, LedgerSettings), RemoteWalletError> {
+        let (version, settings) = self.get_configuration_vector().map(|config| {
+            let version =
+                FirmwareVersion::new(config[2].into(), config[3].into(), config[4].into());
+            let enable_blind_signing = config[0] != 0;
+            let pubkey_display = if config[1] == 0 {
+                PubkeyDisplayMode::Long
+            } else {
+                PubkeyDisplayMode::Short
+            };
+            let settings = LedgerSettings {
+                enable_blind_signing,
+                pubkey_display,
+            };
+            (version, settings)
+        })?;
+        let mut probed = self.probed.write();
+        probed.version = Some(version.clone());
+        probed.settings = Some(settings);
+        Ok((version, settings))
+    }
+
+    fn get_configuration_vector(&self) -> Result<Vec<u8>, RemoteWalletError> {
+        let config = self.send_apdu(commands::GET_APP_CONFIGURATION, 0, 0, &[])?;
+        if config.len() != APP_CONFIGURATION_LEN {
+            return Err(RemoteWalletError::Protocol("Version packet size mismatch"));
+        }
+        Ok(config)
+    }
+
+    fn parse_status(status: u16) -> Result<(), RemoteWalletError> {
         if status == APDU_SUCCESS_CODE {
             Ok(())
-        } else if let Some(err) = LedgerError::from_usize(status) {
-            Err(err.into())
         } else {
-            Err(RemoteWalletError::Protocol("Unknown error"))
+            Err(LedgerError::from_status_word(status).into())
         }
     }
 }
 
 #[cfg(not(feature = "hidapi"))]
-impl RemoteWallet<Self> for LedgerWallet {}
+impl LedgerWallet {
+    pub fn get_settings(&self) -> Result<LedgerSettings, RemoteWalletError> {
+        Err(RemoteWalletError::Hid(
+            "hidapi crate compilation disabled in remote-wallet.".to_string(),
+        ))
+    }
+}
+
 #[cfg(feature = "hidapi")]
-impl RemoteWallet<hidapi::DeviceInfo> for LedgerWallet {
+impl RemoteWallet for LedgerWallet {
     fn name(&self) -> &str {
         "Ledger hardware wallet"
     }
 
-    fn read_device(
-        &mut self,
-        dev_info: &hidapi::DeviceInfo,
-    ) -> Result<RemoteWalletInfo, RemoteWalletError> {
-        let manufacturer = dev_info
-            .manufacturer_string()
-            .and_then(|s| Manufacturer::try_from(s).ok())
-            .unwrap_or_default();
-        let model = dev_info
-            .product_string()
-            .unwrap_or("Unknown")
-            .to_lowercase()
-            .replace(' ', "-");
-        let serial = dev_info.serial_number().unwrap_or("Unknown").to_string();
-        let host_device_path = dev_info.path().to_string_lossy().to_string();
-        let version = self.get_firmware_version()?;
-        self.version = version;
-        let pubkey_result = self.get_pubkey(&DerivationPath::default(), false);
-        let (pubkey, error) = match pubkey_result {
-            Ok(pubkey) => (pubkey, None),
-            Err(err) => (Pubkey::default(), Some(err)),
-        };
-        Ok(RemoteWalletInfo {
-            model,
-            manufacturer,
-            serial,
-            host_device_path,
-            pubkey,
-            error,
-        })
+    fn manufacturer(&self) -> Manufacturer {
+        Manufacturer::Ledger
+    }
+
+    fn pretty_path(&self) -> String {
+        match self.probed.read().base_pubkey {
+            Some(pubkey) => format!("usb://{}/{:?}", Manufacturer::Ledger, pubkey),
+            None => format!("usb://{}", Manufacturer::Ledger),
+        }
+    }
+
+    fn fill_info(&self, info: RemoteWalletInfo) -> RemoteWalletInfo {
+        let probed = self.probed.read();
+        RemoteWalletInfo {
+            pubkey: probed.base_pubkey.unwrap_or_default(),
+            error: probed.base_pubkey_error.clone(),
+            settings: probed.settings,
+            app: probed.app.clone(),
+            ..info
+        }
+    }
+
+    fn can_confirm_keys(&self) -> bool {
+        true
+    }
+
+    fn can_display_addresses(&self) -> bool {
+        true
+    }
+
+    fn max_message_len(&self) -> usize {
+        u16::MAX as usize
+    }
+
+    fn get_base_pubkey(&self) -> Result<Pubkey, RemoteWalletError> {
+        if let Some(pubkey) = self.probed.read().base_pubkey {
+            return Ok(pubkey);
+        }
+        let result = self.get_pubkey(&DerivationPath::default(), false);
+        let mut probed = self.probed.write();
+        match &result {
+            Ok(pubkey) => {
+                probed.base_pubkey = Some(*pubkey);
+                probed.base_pubkey_error = None;
+            }
+            Err(err) => probed.base_pubkey_error = Some(err.clone()),
+        }
+        result
+    }
+
+    /// Read the running app, app configuration and base pubkey from the device, refreshing the
+    /// cached values. Fails if the Spacemesh app isn't running or doesn't respond; a failure to
+    /// read the base pubkey is reported through `RemoteWalletInfo::error` instead.
+    fn probe(&self) -> Result<(), RemoteWalletError> {
+        let _session = self.session();
+        let app = self.get_running_app()?;
+        if !app.is_spacemesh() {
+            return Err(RemoteWalletError::AppNotRunning(app.name));
+        }
+        self.get_configuration()?;
+        self.probed.write().base_pubkey = None;
+        let _ = self.get_base_pubkey();
+        Ok(())
+    }
+
+    fn app(&self) -> Option<&dyn DeviceApp> {
+        Some(self)
+    }
+
+    fn into_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync> {
+        self
     }
 
     fn get_pubkey(
@@ -406,11 +607,7 @@ impl RemoteWallet<hidapi::DeviceInfo> for LedgerWallet {
         let derivation_path = extend_and_serialize(derivation_path);
 
         let key = self.send_apdu(
-            if self.outdated_app() {
-                commands::DEPRECATED_GET_PUBKEY
-            } else {
-                commands::GET_PUBKEY
-            },
+            commands::GET_PUBKEY,
             if confirm_key {
                 P1_CONFIRM
             } else {
@@ -419,20 +616,30 @@ impl RemoteWallet<hidapi::DeviceInfo> for LedgerWallet {
             0,
             &derivation_path,
         )?;
+        if key.len() != PUBKEY_LEN {
+            return Err(RemoteWalletError::Protocol("Key packet size mismatch"));
+        }
         Pubkey::try_from(key).map_err(|_| RemoteWalletError::Protocol("Key packet size mismatch"))
     }
 
+    /// The app doesn't return the address it displays, only the key it was computed from
+    fn display_address(
+        &self,
+        derivation_path: &DerivationPath,
+    ) -> Result<(Pubkey, Option<String>), RemoteWalletError> {
+        self.get_pubkey(derivation_path, true)
+            .map(|pubkey| (pubkey, None))
+    }
+
     fn sign_message(
         &self,
         derivation_path: &DerivationPath,
         data: &[u8],
     ) -> Result<Signature, RemoteWalletError> {
-        let mut payload = if self.outdated_app() {
-            extend_and_serialize(derivation_path)
-        } else {
-            extend_and_serialize_multiple(&[derivation_path])
-        };
-        if data.len() > u16::max_value() as usize {
+        // hold the session across all chunks, the device rejects interleaved requests
+        let _session = self.session();
+        let mut payload = extend_and_serialize_multiple(&[derivation_path]);
+        if data.len() > self.max_message_len() {
             return Err(RemoteWalletError::InvalidInput(
                 "Message to sign is too long".to_string(),
             ));
@@ -440,7 +647,8 @@ impl RemoteWallet<hidapi::DeviceInfo> for LedgerWallet {
 
         // Check to see if this data needs to be split up and
         // sent in chunks.
-        let max_size = MAX_CHUNK_SIZE - payload.len();
+        let max_chunk_size = self.model.max_apdu_data_len();
+        let max_size = max_chunk_size - payload.len();
         let empty = vec![];
         let (data, remaining_data) = if data.len() > max_size {
             data.split_at(max_size)
@@ -449,11 +657,6 @@ impl RemoteWallet<hidapi::DeviceInfo> for LedgerWallet {
         };
 
         // Pack the first chunk
-        if self.outdated_app() {
-            for byte in (data.len() as u16).to_be_bytes().iter() {
-                payload.push(*byte);
-            }
-        }
         payload.extend_from_slice(data);
         trace!("Serialized payload length {:?}", payload.len());
 
@@ -464,56 +667,90 @@ impl RemoteWallet<hidapi::DeviceInfo> for LedgerWallet {
         };
 
         let p1 = P1_CONFIRM;
-        let mut result = self.send_apdu(
-            if self.outdated_app() {
-                commands::DEPRECATED_SIGN_MESSAGE
-            } else {
-                commands::SIGN_MESSAGE
-            },
-            p1,
-            p2,
-            &payload,
-        )?;
+        let mut result = self.send_apdu(commands::SIGN_MESSAGE, p1, p2, &payload)?;
 
         // Pack and send the remaining chunks
         if !remaining_data.is_empty() {
             let mut chunks: Vec<_> = remaining_data
-                .chunks(MAX_CHUNK_SIZE)
+                .chunks(max_chunk_size)
                 .map(|data| {
-                    let mut payload = if self.outdated_app() {
-                        (data.len() as u16).to_be_bytes().to_vec()
-                    } else {
-                        vec![]
-                    };
-                    payload.extend_from_slice(data);
                     let p2 = P2_EXTEND | P2_MORE;
-                    (p2, payload)
+                    (p2, data.to_vec())
                 })
                 .collect();
 
             // Clear the P2_MORE bit on the last item.
             chunks.last_mut().unwrap().0 &= !P2_MORE;
 
-            for (p2, payload) in chunks {
+            let total = chunks.len() + 1;
+            emit(RemoteWalletEvent::ChunkProgress { sent: 1, total });
+            for (index, (p2, payload)) in chunks.into_iter().enumerate() {
                 result = self.send_apdu(commands::SIGN_MESSAGE, p1, p2, &payload)?;
+                emit(RemoteWalletEvent::ChunkProgress {
+                    sent: index + 2,
+                    total,
+                });
             }
         }
 
-        let array: [u8; 64] = result
+        let array: [u8; SIGNATURE_LEN] = result
             .try_into()
             .map_err(|_| RemoteWalletError::Protocol("Signature packet size mismatch"))?;
         Ok(Signature::from(array))
     }
 }
 
+#[cfg(feature = "hidapi")]
+impl DeviceApp for LedgerWallet {
+    fn is_blind_signing_enabled(&self) -> Result<bool, RemoteWalletError> {
+        self.get_settings()
+            .map(|settings| settings.enable_blind_signing)
+    }
+
+    /// Check that the Spacemesh app version is at least `MIN_SUPPORTED_APP_VERSION`
+    fn check_app_version(&self) -> Result<(), RemoteWalletError> {
+        let version = self.get_firmware_version()?;
+        if version < MIN_SUPPORTED_APP_VERSION {
+            return Err(RemoteWalletError::AppUpdateRequired {
+                version,
+                minimum: MIN_SUPPORTED_APP_VERSION,
+            });
+        }
+        Ok(())
+    }
+}
+
 /// Check if the detected device is a valid `Ledger device` by checking both the product ID and the vendor ID
 pub fn is_valid_ledger(vendor_id: u16, product_id: u16) -> bool {
-    let product_ids = [
-        LEDGER_NANO_S_PIDS,
-        LEDGER_NANO_X_PIDS,
-        LEDGER_NANO_S_PLUS_PIDS,
-    ];
-    vendor_id == LEDGER_VID && product_ids.iter().any(|pids| pids.contains(&product_id))
+    vendor_id == LEDGER_VID && LedgerModel::from_product_id(product_id).is_some()
+}
+
+/// Driver for Ledger devices running the Spacemesh app
+#[derive(Debug, Default)]
+pub struct LedgerDriver;
+
+impl RemoteWalletDriver for LedgerDriver {
+    fn manufacturer(&self) -> Manufacturer {
+        Manufacturer::Ledger
+    }
+
+    #[cfg(feature = "hidapi")]
+    fn is_supported(&self, device_info: &hidapi::DeviceInfo) -> bool {
+        is_valid_hid_device(device_info.usage_page(), device_info.interface_number())
+            && is_valid_ledger(device_info.vendor_id(), device_info.product_id())
+    }
+
+    #[cfg(feature = "hidapi")]
+    fn open(
+        &self,
+        usb: &hidapi::HidApi,
+        device_info: &hidapi::DeviceInfo,
+    ) -> Result<(Arc<dyn RemoteWallet>, RemoteWalletInfo), RemoteWalletError> {
+        let device = usb.open_path(device_info.path())?;
+        let mut ledger = LedgerWallet::new(device);
+        let info = ledger.read_device(device_info)?;
+        Ok((Arc::new(ledger), info))
+    }
 }
 
 /// Build the derivation path byte array from a DerivationPath selection
@@ -534,6 +771,8 @@ fn extend_and_serialize(derivation_path: &DerivationPath) -> Vec<u8> {
     concat_derivation
 }
 
+/// Build the signer list sent with `commands::SIGN_MESSAGE`: the number of derivation paths, then
+/// each serialized path
 fn extend_and_serialize_multiple(derivation_paths: &[&DerivationPath]) -> Vec<u8> {
     let mut concat_derivation = vec![derivation_paths.len() as u8];
     for derivation_path in derivation_paths {
@@ -542,53 +781,16 @@ fn extend_and_serialize_multiple(derivation_paths: &[&DerivationPath]) -> Vec<u8
     concat_derivation
 }
 
-/// Choose a Ledger wallet based on matching info fields
+/// Choose a Ledger wallet based on matching info fields, using `selection` to pick between
+/// several matching devices
 pub fn get_ledger_from_info(
     info: RemoteWalletInfo,
     keypair_name: &str,
     wallet_manager: &RemoteWalletManager,
+    selection: &DeviceSelection,
 ) -> Result<Arc<LedgerWallet>, RemoteWalletError> {
-    let devices = wallet_manager.list_devices();
-    let mut matches = devices
-        .iter()
-        .filter(|&device_info| device_info.matches(&info));
-    if matches
-        .clone()
-        .all(|device_info| device_info.error.is_some())
-    {
-        let first_device = matches.next();
-        if let Some(device) = first_device {
-            return Err(device.error.clone().unwrap());
-        }
-    }
-    let mut matches: Vec<(String, String)> = matches
-        .filter(|&device_info| device_info.error.is_none())
-        .map(|device_info| {
-            let query_item = format!("{} ({})", device_info.get_pretty_path(), device_info.model,);
-            (device_info.host_device_path.clone(), query_item)
-        })
-        .collect();
-    if matches.is_empty() {
-        return Err(RemoteWalletError::NoDeviceFound);
-    }
-    matches.sort_by(|a, b| a.1.cmp(&b.1));
-    let (host_device_paths, items): (Vec<String>, Vec<String>) = matches.into_iter().unzip();
-
-    let wallet_host_device_path = if host_device_paths.len() > 1 {
-        let selection = Select::with_theme(&ColorfulTheme::default())
-            .with_prompt(&format!(
-                "Multiple hardware wallets found. Please select a device for {:?}",
-                keypair_name
-            ))
-            .default(0)
-            .items(&items[..])
-            .interact()
-            .unwrap();
-        &host_device_paths[selection]
-    } else {
-        &host_device_paths[0]
-    };
-    wallet_manager.get_ledger(wallet_host_device_path)
+    let info = wallet_manager.select_device(info, keypair_name, selection)?;
+    wallet_manager.get_ledger(&info.host_device_path)
 }
 
 //
@@ -597,8 +799,254 @@ fn is_last_part(p2: u8) -> bool {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
+    #[cfg(feature = "hidapi")]
+    use {
+        crate::remote_keypair::{PayloadType, RemoteKeypair},
+        spacemesh_signer::{Signer, SoftwareKeypair},
+        std::{collections::VecDeque, thread, time::Duration},
+    };
+
+    /// Ledger device running the Spacemesh app, signing with a software key and recording the
+    /// APDUs it receives. It flags requests that interleave with another one: an APDU sent before
+    /// the response to the previous one was read, or in the middle of a chunked message.
+    #[cfg(feature = "hidapi")]
+    pub(crate) struct MockDevice {
+        pub(crate) keypair: SoftwareKeypair,
+        state: Arc<Mutex<MockState>>,
+    }
+
+    #[cfg(feature = "hidapi")]
+    #[derive(Default)]
+    struct MockState {
+        /// APDU being reassembled from the reports written so far, and its announced length
+        partial: Vec<u8>,
+        partial_len: usize,
+        apdus: Vec<Vec<u8>>,
+        reports: VecDeque<Vec<u8>>,
+        /// Message received so far, while more chunks are expected
+        signing: Option<Vec<u8>>,
+        overlap: bool,
+    }
+
+    #[cfg(feature = "hidapi")]
+    impl MockDevice {
+        pub(crate) fn new() -> Self {
+            Self {
+                keypair: SoftwareKeypair::from_seed(&[6u8; 64], DerivationPath::default()).unwrap(),
+                state: Arc::default(),
+            }
+        }
+
+        /// Response to a complete APDU, without its status word
+        fn respond(&self, state: &mut MockState, apdu: &[u8]) -> Vec<u8> {
+            let (p2, data) = (apdu[3], &apdu[5..]);
+            if (apdu[0], apdu[1]) != (APDU_CLA, commands::SIGN_MESSAGE) {
+                state.overlap |= state.signing.is_some();
+            } else if p2 & P2_EXTEND == 0 {
+                state.overlap |= state.signing.is_some();
+                // skip the signer count and the single signer's derivation path
+                state.signing = Some(data[2 + 4 * data[1] as usize..].to_vec());
+            } else {
+                state.overlap |= state.signing.is_none();
+                state
+                    .signing
+                    .get_or_insert_with(Vec::new)
+                    .extend_from_slice(data);
+            }
+            match (apdu[0], apdu[1]) {
+                (dashboard::CLA, dashboard::GET_APP_AND_VERSION) => {
+                    [&[1, 9][..], b"Spacemesh", &[5], b"0.1.0"].concat()
+                }
+                (APDU_CLA, commands::GET_APP_CONFIGURATION) => vec![1, 0, 0, 1, 0],
+                (APDU_CLA, commands::GET_PUBKEY) => self.keypair.pubkey().to_bytes().to_vec(),
+                (APDU_CLA, commands::SIGN_MESSAGE) if is_last_part(p2) => {
+                    let message = state.signing.take().unwrap();
+                    self.keypair.sign_message(&message).to_bytes().to_vec()
+                }
+                _ => vec![],
+            }
+        }
+
+        /// Split a response into reports, the way the device sends it
+        fn reports(response: &[u8]) -> Vec<Vec<u8>> {
+            let message = [&(response.len() as u16).to_be_bytes()[..], response].concat();
+            message
+                .chunks(64 - LEDGER_TRANSPORT_HEADER_LEN)
+                .enumerate()
+                .map(|(seq, chunk)| {
+                    let mut report = vec![0; HID_PREFIX_ZERO];
+                    report.extend_from_slice(&[0x01, 0x01, APDU_TAG]);
+                    report.extend_from_slice(&(seq as u16).to_be_bytes());
+                    report.extend_from_slice(chunk);
+                    report
+                })
+                .collect()
+        }
+    }
+
+    #[cfg(feature = "hidapi")]
+    impl LedgerTransport for MockDevice {
+        fn write(&self, data: &[u8]) -> Result<usize, RemoteWalletError> {
+            let report = &data[HID_PREFIX_ZERO..];
+            let mut state = self.state.lock();
+            if report[3..5] == [0, 0] {
+                state.partial_len = u16::from_be_bytes([report[5], report[6]]) as usize;
+                state.partial = report[7..].to_vec();
+            } else {
+                state.partial.extend_from_slice(&report[5..]);
+            }
+            if state.partial.len() >= state.partial_len {
+                let len = state.partial_len;
+                let apdu: Vec<u8> = state.partial.drain(..).take(len).collect();
+                state.overlap |= !state.reports.is_empty();
+                let mut response = self.respond(&mut state, &apdu);
+                response.extend_from_slice(&APDU_SUCCESS_CODE.to_be_bytes());
+                state.reports.extend(Self::reports(&response));
+                state.apdus.push(apdu);
+            }
+            Ok(data.len())
+        }
+
+        fn read(&self, buf: &mut [u8]) -> Result<usize, RemoteWalletError> {
+            // leave other threads time to interleave their requests
+            thread::sleep(Duration::from_micros(100));
+            let report = self.state.lock().reports.pop_front().ok_or_else(|| {
+                RemoteWalletError::Hid("read without a pending response".to_string())
+            })?;
+            buf[..report.len()].copy_from_slice(&report);
+            Ok(report.len())
+        }
+    }
+
+    #[cfg(feature = "hidapi")]
+    fn mock_ledger() -> (LedgerWallet, Arc<Mutex<MockState>>) {
+        let device = MockDevice::new();
+        let state = device.state.clone();
+        (LedgerWallet::new(device), state)
+    }
+
+    #[cfg(feature = "hidapi")]
+    #[test]
+    fn test_apdu_bytes() {
+        let (ledger, state) = mock_ledger();
+        let apdus = || std::mem::take(&mut state.lock().apdus);
+        // 44'/540'
+        let path = [0x80, 0, 0, 44, 0x80, 0, 0x02, 0x1c];
+
+        ledger.get_running_app().unwrap();
+        assert_eq!(apdus(), [vec![0xb0, 0x01, 0x00, 0x00, 0x00]]);
+
+        ledger.get_settings().unwrap();
+        assert_eq!(apdus(), [vec![0xe0, 0x04, 0x00, 0x00, 0x00]]);
+
+        ledger
+            .get_pubkey(&DerivationPath::default(), false)
+            .unwrap();
+        let get_pubkey = [&[0xe0, 0x05, 0x00, 0x00, 0x09, 0x02][..], &path].concat();
+        assert_eq!(apdus(), [get_pubkey]);
+
+        ledger.display_address(&DerivationPath::default()).unwrap();
+        let display = [&[0xe0, 0x05, 0x01, 0x00, 0x09, 0x02][..], &path].concat();
+        assert_eq!(apdus(), [display]);
+
+        ledger
+            .sign_message(&DerivationPath::default(), &[0xaa; 3])
+            .unwrap();
+        let sign = [
+            &[0xe0, 0x06, 0x01, 0x00, 0x0d, 0x01, 0x02][..],
+            &path,
+            &[0xaa; 3],
+        ]
+        .concat();
+        assert_eq!(apdus(), [sign]);
+
+        // 245 bytes fit next to the signer list in the first chunk, then 255 per chunk
+        ledger
+            .sign_message(&DerivationPath::default(), &[0xaa; 600])
+            .unwrap();
+        let first = [
+            &[0xe0, 0x06, 0x01, 0x02, 0xff, 0x01, 0x02][..],
+            &path,
+            &[0xaa; 245],
+        ]
+        .concat();
+        let middle = [&[0xe0, 0x06, 0x01, 0x03, 0xff][..], &[0xaa; 255]].concat();
+        let last = [&[0xe0, 0x06, 0x01, 0x01, 0x64][..], &[0xaa; 100]].concat();
+        assert_eq!(apdus(), [first, middle, last]);
+    }
+
+    #[cfg(feature = "hidapi")]
+    #[test]
+    fn test_concurrent_signing() {
+        let (ledger, state) = mock_ledger();
+        let keypair = RemoteKeypair::new(
+            Arc::new(ledger),
+            DerivationPath::default(),
+            false,
+            "usb://ledger".to_string(),
+        )
+        .unwrap();
+
+        // long messages are sent in several chunks, which must not interleave
+        thread::scope(|scope| {
+            for i in 0..4u8 {
+                let keypair = &keypair;
+                scope.spawn(move || {
+                    for j in 0..4u8 {
+                        let message = vec![i * 4 + j; 600];
+                        let signature = keypair
+                            .sign_payload(&message, PayloadType::Transaction)
+                            .unwrap();
+                        assert!(signature.verify(&keypair.pubkey, &message));
+                    }
+                });
+            }
+        });
+        let state = state.lock();
+        assert!(!state.overlap);
+        assert_eq!(state.apdus.len(), 1 + 16 * 3);
+    }
+
+    #[test]
+    fn test_parse_app_and_version() {
+        let response = [&[1, 9][..], b"Spacemesh", &[5], b"0.1.0", &[1, 0x02]].concat();
+        let app = parse_app_and_version(&response).unwrap();
+        assert_eq!(
+            app,
+            RunningApp {
+                name: SPACEMESH_APP_NAME.to_string(),
+                version: "0.1.0".to_string(),
+            }
+        );
+        assert!(app.is_spacemesh());
+        assert!(!app.needs_update());
+
+        let response = [&[1, 5][..], b"BOLOS", &[5], b"2.2.3"].concat();
+        let app = parse_app_and_version(&response).unwrap();
+        assert!(app.is_dashboard());
+        assert!(!app.needs_update());
+
+        let outdated = RunningApp {
+            name: SPACEMESH_APP_NAME.to_string(),
+            version: "0.0.9".to_string(),
+        };
+        assert!(outdated.needs_update());
+
+        assert!(parse_app_and_version(&[]).is_err());
+        assert!(parse_app_and_version(&[2, 0, 0]).is_err());
+        assert!(parse_app_and_version(&[1, 9, b'S']).is_err());
+    }
+
+    #[test]
+    fn test_is_valid_ledger() {
+        assert!(is_valid_ledger(LEDGER_VID, 0x1011));
+        assert!(is_valid_ledger(LEDGER_VID, 0x6011));
+        assert!(is_valid_ledger(LEDGER_VID, 0x7015));
+        assert!(!is_valid_ledger(LEDGER_VID, 0x2011));
+        assert!(!is_valid_ledger(0x1209, 0x1011));
+    }
 
     #[test]
     fn test_is_last_part() {
@@ -624,6 +1072,19 @@ mod tests {
         assert!(is_last_part(p2 & !P2_MORE));
     }
 
+    #[test]
+    fn test_extend_and_serialize() {
+        let path = DerivationPath::new_bip44(Some(1), Some(2));
+        assert_eq!(
+            extend_and_serialize(&path),
+            vec![4, 0x80, 0, 0, 44, 0x80, 0, 0x02, 0x1c, 0x80, 0, 0, 1, 0x80, 0, 0, 2]
+        );
+        assert_eq!(
+            extend_and_serialize(&DerivationPath::default()),
+            vec![2, 0x80, 0, 0, 44, 0x80, 0, 0x02, 0x1c]
+        );
+    }
+
     #[test]
     fn test_parse_status() {
         LedgerWallet::parse_status(APDU_SUCCESS_CODE).expect("unexpected result");
@@ -631,8 +1092,18 @@ mod tests {
         {
             assert_eq!(err, LedgerError::UserCancel);
         }
-        if let RemoteWalletError::Protocol(err) = LedgerWallet::parse_status(0x6fff).unwrap_err() {
-            assert_eq!(err, "Unknown error");
+        if let RemoteWalletError::LedgerError(err) = LedgerWallet::parse_status(0x6a80).unwrap_err()
+        {
+            assert_eq!(err, LedgerError::InvalidMessage);
+        }
+        if let RemoteWalletError::LedgerError(err) = LedgerWallet::parse_status(0x5515).unwrap_err()
+        {
+            assert_eq!(err, LedgerError::DeviceLocked);
+        }
+        let err = LedgerWallet::parse_status(0x6fff).unwrap_err();
+        assert_eq!(err.status_word(), Some(0x6fff));
+        if let RemoteWalletError::LedgerError(err) = err {
+            assert_eq!(err, LedgerError::Unknown(0x6fff));
         }
     }
 }
@@ -1,8 +1,48 @@
-use {num_derive::FromPrimitive, thiserror::Error};
+use thiserror::Error;
+
+/// Declares `LedgerError` with one variant per known status word, plus `Unknown` to preserve
+/// status words that aren't listed
+macro_rules! ledger_errors {
+    ($($(#[$meta:meta])* $name:ident = $status:literal,)*) => {
+        #[derive(Error, Debug, Clone, PartialEq, Eq)]
+        pub enum LedgerError {
+            $($(#[$meta])* $name,)*
+
+            #[error("Ledger device returned unknown status word 0x{0:04x}")]
+            Unknown(u16),
+        }
+
+        impl LedgerError {
+            /// Map a status word returned by the device to an error
+            pub fn from_status_word(status: u16) -> Self {
+                match status {
+                    $($status => Self::$name,)*
+                    _ => Self::Unknown(status),
+                }
+            }
+
+            /// Raw status word returned by the device
+            pub fn status_word(&self) -> u16 {
+                match self {
+                    $(Self::$name => $status,)*
+                    Self::Unknown(status) => *status,
+                }
+            }
+        }
+    };
+}
+
+ledger_errors! {
+    #[error("Ledger operation rejected by the user")]
+    UserRefused = 0x5501,
+
+    #[error("Ledger device is locked, unlock it with your PIN and retry")]
+    DeviceLocked = 0x5515,
+
+    #[error("Spacemesh app not open on Ledger device, a different app is running")]
+    WrongApp = 0x6511,
 
-#[derive(Error, Debug, Clone, FromPrimitive, PartialEq, Eq)]
-pub enum LedgerError {
-    #[error("Solana app not open on Ledger device")]
+    #[error("Spacemesh app not open on Ledger device")]
     NoAppResponse = 0x6700,
 
     #[error("Ledger sdk exception")]
@@ -62,24 +102,80 @@ pub enum LedgerError {
     #[error("Ledger out of space")]
     SdkNotEnoughSpace = 0x6819,
 
-    #[error("Ledger invalid counter")]
+    #[error("Ledger security status not satisfied, unlock the device and retry")]
     NoApduReceived = 0x6982,
 
     #[error("Ledger operation rejected by the user")]
     UserCancel = 0x6985,
 
-    #[error("Ledger received invalid Solana message")]
-    SolanaInvalidMessage = 0x6a80,
-
-    #[error("Solana summary finalization failed on Ledger device")]
-    SolanaSummaryFinalizeFailed = 0x6f00,
-
-    #[error("Solana summary update failed on Ledger device")]
-    SolanaSummaryUpdateFailed = 0x6f01,
+    #[error("Spacemesh app could not parse the message to sign")]
+    InvalidMessage = 0x6a80,
 
     #[error("Ledger received unimplemented instruction")]
     UnimplementedInstruction = 0x6d00,
 
+    #[error("Spacemesh app not open on Ledger device, the dashboard is running")]
+    DashboardUnknownInstruction = 0x6d02,
+
     #[error("Ledger received invalid CLA")]
     InvalidCla = 0x6e00,
+
+    #[error("Spacemesh app not open on Ledger device, open it and retry")]
+    AppNotOpen = 0x6e01,
+
+    #[error("Spacemesh app failed to finalize the transaction summary")]
+    SummaryFinalizeFailed = 0x6f00,
+
+    #[error("Spacemesh app failed to update the transaction summary")]
+    SummaryUpdateFailed = 0x6f01,
+
+    #[error("Ledger device halted, disconnect and reconnect it")]
+    DeviceHalted = 0x6faa,
+}
+
+impl LedgerError {
+    /// Whether the error means the Spacemesh app isn't running on an otherwise reachable device
+    pub fn is_app_not_open(&self) -> bool {
+        matches!(
+            self,
+            Self::NoAppResponse
+                | Self::WrongApp
+                | Self::AppNotOpen
+                | Self::DashboardUnknownInstruction
+                | Self::InvalidCla
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_word() {
+        assert_eq!(
+            LedgerError::from_status_word(0x6985),
+            LedgerError::UserCancel
+        );
+        assert_eq!(
+            LedgerError::from_status_word(0x5515),
+            LedgerError::DeviceLocked
+        );
+        assert_eq!(
+            LedgerError::from_status_word(0x6e01),
+            LedgerError::AppNotOpen
+        );
+        assert_eq!(LedgerError::UserCancel.status_word(), 0x6985);
+        assert_eq!(LedgerError::WrongApp.status_word(), 0x6511);
+        assert!(LedgerError::AppNotOpen.is_app_not_open());
+        assert!(!LedgerError::DeviceLocked.is_app_not_open());
+
+        let unknown = LedgerError::from_status_word(0x6fff);
+        assert_eq!(unknown, LedgerError::Unknown(0x6fff));
+        assert_eq!(unknown.status_word(), 0x6fff);
+        assert_eq!(
+            unknown.to_string(),
+            "Ledger device returned unknown status word 0x6fff"
+        );
+    }
 }
@@ -0,0 +1,175 @@
+use {std::str::FromStr, thiserror::Error};
+
+/// Largest APDU payload the device accepts. Every current model only accepts short APDUs.
+const MAX_SHORT_APDU_DATA_LEN: usize = 255;
+
+/// Ledger device model, derived from the USB product ID
+/// cbindgen:ignore
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum LedgerModel {
+    #[default]
+    Unknown,
+    NanoS,
+    NanoX,
+    NanoSPlus,
+    Stax,
+    Flex,
+}
+
+/// Kind of screen a Ledger device has
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScreenType {
+    /// Small monochrome screen navigated with buttons
+    Monochrome,
+    /// E-ink touchscreen
+    EInkTouchscreen,
+}
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+#[error("unknown Ledger model `{0}`")]
+pub struct LedgerModelError(String);
+
+impl LedgerModel {
+    /// Models that can be detected
+    pub const ALL: [LedgerModel; 5] = [
+        LedgerModel::NanoS,
+        LedgerModel::NanoX,
+        LedgerModel::NanoSPlus,
+        LedgerModel::Stax,
+        LedgerModel::Flex,
+    ];
+
+    /// Detect the model from a USB product ID. Devices report either a legacy product ID, equal
+    /// to the model ID, or a product ID whose upper byte identifies the model and whose lower byte
+    /// identifies the USB interfaces in use.
+    pub fn from_product_id(product_id: u16) -> Option<Self> {
+        match (product_id, product_id >> 8) {
+            (0x0001, _) | (_, 0x10) => Some(Self::NanoS),
+            (0x0004, _) | (_, 0x40) => Some(Self::NanoX),
+            (0x0005, _) | (_, 0x50) => Some(Self::NanoSPlus),
+            (0x0006, _) | (_, 0x60) => Some(Self::Stax),
+            (0x0007, _) | (_, 0x70) => Some(Self::Flex),
+            _ => None,
+        }
+    }
+
+    pub fn screen(&self) -> ScreenType {
+        match self {
+            Self::Stax | Self::Flex => ScreenType::EInkTouchscreen,
+            Self::Unknown | Self::NanoS | Self::NanoX | Self::NanoSPlus => ScreenType::Monochrome,
+        }
+    }
+
+    /// Largest payload a single APDU sent to this model may carry
+    pub fn max_apdu_data_len(&self) -> usize {
+        match self {
+            Self::Unknown
+            | Self::NanoS
+            | Self::NanoX
+            | Self::NanoSPlus
+            | Self::Stax
+            | Self::Flex => MAX_SHORT_APDU_DATA_LEN,
+        }
+    }
+}
+
+impl AsRef<str> for LedgerModel {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Unknown => "unknown",
+            Self::NanoS => "nano-s",
+            Self::NanoX => "nano-x",
+            Self::NanoSPlus => "nano-s-plus",
+            Self::Stax => "stax",
+            Self::Flex => "flex",
+        }
+    }
+}
+
+impl std::fmt::Display for LedgerModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let s: &str = self.as_ref();
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for LedgerModel {
+    type Err = LedgerModelError;
+    /// Parse a model name, ignoring case and separators, e.g. `nano-s-plus` or `Nano S Plus`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized: String = s
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        match normalized.as_str() {
+            "nanos" => Ok(Self::NanoS),
+            "nanox" => Ok(Self::NanoX),
+            "nanosplus" | "nanosp" => Ok(Self::NanoSPlus),
+            "stax" => Ok(Self::Stax),
+            "flex" => Ok(Self::Flex),
+            _ => Err(LedgerModelError(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_product_id() {
+        assert_eq!(
+            LedgerModel::from_product_id(0x0001),
+            Some(LedgerModel::NanoS)
+        );
+        assert_eq!(
+            LedgerModel::from_product_id(0x1011),
+            Some(LedgerModel::NanoS)
+        );
+        assert_eq!(
+            LedgerModel::from_product_id(0x4015),
+            Some(LedgerModel::NanoX)
+        );
+        assert_eq!(
+            LedgerModel::from_product_id(0x0005),
+            Some(LedgerModel::NanoSPlus)
+        );
+        assert_eq!(
+            LedgerModel::from_product_id(0x6011),
+            Some(LedgerModel::Stax)
+        );
+        assert_eq!(
+            LedgerModel::from_product_id(0x0007),
+            Some(LedgerModel::Flex)
+        );
+        assert_eq!(
+            LedgerModel::from_product_id(0x7015),
+            Some(LedgerModel::Flex)
+        );
+        assert_eq!(LedgerModel::from_product_id(0x0002), None);
+        assert_eq!(LedgerModel::from_product_id(0x2001), None);
+    }
+
+    #[test]
+    fn test_model_str() {
+        for model in LedgerModel::ALL {
+            assert_eq!(model.to_string().parse(), Ok(model));
+        }
+        assert_eq!("Nano S Plus".parse(), Ok(LedgerModel::NanoSPlus));
+        assert_eq!("NANOX".parse(), Ok(LedgerModel::NanoX));
+        assert_eq!(
+            "nano-z".parse::<LedgerModel>(),
+            Err(LedgerModelError("nano-z".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_capabilities() {
+        assert_eq!(LedgerModel::NanoS.screen(), ScreenType::Monochrome);
+        assert_eq!(LedgerModel::Stax.screen(), ScreenType::EInkTouchscreen);
+        for model in LedgerModel::ALL {
+            assert_eq!(model.max_apdu_data_len(), 255);
+        }
+    }
+}
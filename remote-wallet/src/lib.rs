@@ -1,25 +1,51 @@
-#![allow(clippy::integer_arithmetic)]
+#![allow(clippy::arithmetic_side_effects)]
 #![allow(dead_code)]
 
+pub mod events;
+pub mod keystore;
 pub mod ledger;
 pub mod ledger_error;
+pub mod ledger_model;
 pub mod locator;
+pub mod monitor;
 pub mod remote_keypair;
 pub mod remote_wallet;
+pub mod signer_source;
+pub mod watch_only;
 
-use std::ffi::{c_char, CStr};
+use std::ffi::{c_char, c_void, CStr};
+use std::fmt;
 use std::ops::Deref;
+use std::sync::Arc;
 use {
+    events::{CallbackEventSink, NullEventSink, RemoteWalletEventCallback, RemoteWalletEventSink},
+    ledger::LedgerSettings,
+    remote_wallet::RemoteWalletError,
     spacemesh_derivation_path::DerivationPath,
-    solana_sdk::pubkey::{Pubkey, PUBKEY_BYTES},
+    spacemesh_signer::{OffchainMessage, Pubkey, Signature, PUBKEY_BYTES, SIGNATURE_BYTES},
 };
 
+/// set_event_callback registers a callback that receives user-interaction events, such as the
+/// device waiting for the user's approval, instead of having them printed to stdout. user_data is
+/// passed back to the callback unchanged. Passing a NULL callback silences all events.
+#[no_mangle]
+pub extern "C" fn set_event_callback(callback: RemoteWalletEventCallback, user_data: *mut c_void) {
+    let sink: Arc<dyn RemoteWalletEventSink> = match callback {
+        Some(callback) => Arc::new(CallbackEventSink::new(callback, user_data)),
+        None => Arc::new(NullEventSink),
+    };
+    events::set_event_sink(sink);
+}
+
 /// read_pubkey_from_ledger reads a pubkey from the ledger device specified by path and
-/// derivation_path. If path is empty, the first ledger device found will be used. If confirm_key
+/// derivation_path. If path is empty, the first ledger device found will be used. path may select
+/// a device by serial number, model or host path, e.g. usb://ledger?serial=0001. If confirm_key
 /// is true, it will prompt the user to confirm the key on the device. It writes the pubkey bytes
 /// to result, which must be at least 32 bytes long. It returns a status code, with a return value
-/// of zero indicating success.
+/// of zero indicating success. If the device returned an error, the status code is the status word
+/// it returned (e.g. 0x5515 if the device is locked); any other error returns 1.
 #[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub extern "C" fn read_pubkey_from_ledger(
     path: *const c_char,
     derivation_path_ptr: *const c_char,
@@ -34,7 +60,7 @@ pub extern "C" fn read_pubkey_from_ledger(
         }
         Err(err) => {
             eprintln!("{}", err);
-            1
+            err.code()
         }
     }
 }
@@ -43,25 +69,136 @@ fn _read_pubkey_from_ledger(
     path: *const c_char,
     derivation_path_ptr: *const c_char,
     confirm_key: bool,
-) -> Result<Pubkey, Box<dyn std::error::Error>> {
-    // first handle the device path
-    // note: it might seem to make more sense to do all the unsafe operations in the parent
-    // function and leave only the "business logic" here, but doing it here allows us to handle
-    // all errors at the higher level and pass them back across FFI.
-    let path_str = unsafe { CStr::from_ptr(path) };
-    let path_str = path_str
-        .to_str()
-        .map_err(|e| format!("converting path string: {e}"))?;
+) -> Result<Pubkey, FfiError> {
+    let keypair = _remote_keypair_from_path(path, derivation_path_ptr, confirm_key)?;
+    Ok(keypair.pubkey)
+}
 
-    // if no path specified, default to first ledger device
-    let path_str = if path_str.is_empty() {
-        "usb://ledger"
-    } else {
-        path_str
+/// sign_offchain_message_with_ledger signs the message_len bytes at message as an off-chain
+/// message, which can't be replayed as a transaction, with the key at derivation_path on the
+/// ledger device specified by path. If path is empty, the first ledger device found will be used.
+/// Blind signing must be enabled in the Spacemesh app. It writes the signature to result, which
+/// must be at least 64 bytes long. It returns a status code, with a return value of zero indicating
+/// success. If the device returned an error, the status code is the status word it returned; any
+/// other error returns 1.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn sign_offchain_message_with_ledger(
+    path: *const c_char,
+    derivation_path_ptr: *const c_char,
+    message: *const u8,
+    message_len: usize,
+    result: *mut u8,
+) -> u16 {
+    let (Some(message), false) = (slice_from_c(message, message_len), result.is_null()) else {
+        eprintln!("unexpected NULL pointer");
+        return 1;
     };
+    match _sign_offchain_message_with_ledger(path, derivation_path_ptr, message) {
+        Ok(signature) => {
+            let result_slice = unsafe { std::slice::from_raw_parts_mut(result, SIGNATURE_BYTES) };
+            result_slice.copy_from_slice(signature.as_ref());
+            0
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            err.code()
+        }
+    }
+}
+
+/// Borrow the len bytes at ptr. A NULL ptr is only accepted for an empty slice.
+fn slice_from_c<'a>(ptr: *const u8, len: usize) -> Option<&'a [u8]> {
+    match (ptr.is_null(), len) {
+        (true, 0) => Some(&[]),
+        (true, _) => None,
+        (false, _) => Some(unsafe { std::slice::from_raw_parts(ptr, len) }),
+    }
+}
+
+fn _sign_offchain_message_with_ledger(
+    path: *const c_char,
+    derivation_path_ptr: *const c_char,
+    message: &[u8],
+) -> Result<Signature, FfiError> {
+    let message = OffchainMessage::new(message).map_err(|e| format!("invalid message: {e}"))?;
+    let keypair = _remote_keypair_from_path(path, derivation_path_ptr, false)?;
+    keypair
+        .sign_offchain_message(&message)
+        .map_err(|e| FfiError::new("signing off-chain message", e))
+}
+
+/// Status code returned by verify_address_on_ledger if the address displayed by the device doesn't
+/// match the one computed from its pubkey
+pub const ADDRESS_MISMATCH: u16 = 2;
+
+/// Status code returned by verify_address_on_ledger if the device displayed an address for
+/// another network, i.e. with another hrp
+pub const NETWORK_MISMATCH: u16 = 3;
+
+/// verify_address_on_ledger asks the ledger device specified by path to display the address of
+/// the wallet account controlled by the key at derivation_path, for the user to compare with the
+/// address shown by the app, and checks it against the address computed from the key, encoded with
+/// hrp (e.g. "sm"). If path is empty, the first ledger device found will be used. It writes the
+/// address, NUL-terminated, to result, which must be at least result_cap bytes long; 64 bytes are
+/// enough for addresses with short hrps. It returns a status code, with a return value of zero
+/// indicating success. If the device displayed an address for another hrp, it returns
+/// NETWORK_MISMATCH; if the addresses otherwise don't match, it returns ADDRESS_MISMATCH. In both
+/// cases the address must not be used. If the device returned an error, the status code is the
+/// status word it returned (e.g. 0x5501 if the user rejected the address); any other error
+/// returns 1.
+#[no_mangle]
+pub extern "C" fn verify_address_on_ledger(
+    path: *const c_char,
+    derivation_path_ptr: *const c_char,
+    hrp: *const c_char,
+    result: *mut c_char,
+    result_cap: usize,
+) -> u16 {
+    match _verify_address_on_ledger(path, derivation_path_ptr, hrp) {
+        Ok(address) if address.len() < result_cap => {
+            let result_slice =
+                unsafe { std::slice::from_raw_parts_mut(result as *mut u8, address.len() + 1) };
+            result_slice[..address.len()].copy_from_slice(address.as_bytes());
+            result_slice[address.len()] = 0;
+            0
+        }
+        Ok(address) => {
+            eprintln!(
+                "result buffer too small: {} bytes, {} needed",
+                result_cap,
+                address.len() + 1
+            );
+            1
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            err.code()
+        }
+    }
+}
+
+fn _verify_address_on_ledger(
+    path: *const c_char,
+    derivation_path_ptr: *const c_char,
+    hrp: *const c_char,
+) -> Result<String, FfiError> {
+    let hrp = unsafe { CStr::from_ptr(hrp) }
+        .to_str()
+        .map_err(|e| format!("converting hrp string: {e}"))?;
+    let keypair = _remote_keypair_from_path(path, derivation_path_ptr, false)?;
+    keypair
+        .verify_address(hrp)
+        .map_err(|e| FfiError::new("verifying address", e))
+}
 
-    let locator = locator::Locator::new_from_path(path_str)
-        .map_err(|e| format!("creating locator from path string: {e}"))?;
+fn _remote_keypair_from_path(
+    path: *const c_char,
+    derivation_path_ptr: *const c_char,
+    confirm_key: bool,
+) -> Result<remote_keypair::RemoteKeypair, FfiError> {
+    // first handle the device path
+    let locator = _locator_from_path(path)?;
 
     // next handle the derivation path
     // note: ed25519-bip32 performs more validation on the derivation path. we don't do so here
@@ -76,19 +213,128 @@ fn _read_pubkey_from_ledger(
         .map_err(|e| format!("creating derivation path from string: {e}"))?;
 
     let wm = remote_wallet::maybe_wallet_manager()
-        .map_err(|e| format!("getting wallet manager: {e}"))?
+        .map_err(|e| FfiError::new("getting wallet manager", e))?
         .ok_or("failed to get wallet manager")?;
 
-    let keypair = remote_keypair::generate_remote_keypair(
+    remote_keypair::generate_remote_keypair(
         locator,
         derivation_path,
         wm.deref(),
         confirm_key,
         "main",
+        &remote_wallet::DeviceSelection::FirstMatch,
     )
-    .map_err(|e| format!("generating remote keypair: {e}"))?;
+    .map_err(|e| FfiError::new("generating remote keypair", e))
+}
 
-    Ok(keypair.pubkey)
+/// read_settings_from_ledger reads the Spacemesh app settings, such as whether blind signing is
+/// enabled, from the ledger device specified by path. If path is empty, the first ledger device
+/// found will be used. It writes the settings to result. It returns a status code, with a return
+/// value of zero indicating success. If the device returned an error, the status code is the status
+/// word it returned; any other error returns 1.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn read_settings_from_ledger(
+    path: *const c_char,
+    result: *mut LedgerSettings,
+) -> u16 {
+    match _read_settings_from_ledger(path) {
+        Ok(settings) => {
+            unsafe { *result = settings };
+            0
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            err.code()
+        }
+    }
+}
+
+fn _read_settings_from_ledger(path: *const c_char) -> Result<LedgerSettings, FfiError> {
+    let locator = _locator_from_path(path)?;
+
+    let wm = remote_wallet::maybe_wallet_manager()
+        .map_err(|e| FfiError::new("getting wallet manager", e))?
+        .ok_or("failed to get wallet manager")?;
+
+    let ledger = ledger::get_ledger_from_info(
+        remote_wallet::RemoteWalletInfo::parse_locator(locator),
+        "main",
+        wm.deref(),
+        &remote_wallet::DeviceSelection::FirstMatch,
+    )
+    .map_err(|e| FfiError::new("getting ledger device", e))?;
+
+    ledger
+        .get_settings()
+        .map_err(|e| FfiError::new("reading ledger settings", e))
+}
+
+fn _locator_from_path(path: *const c_char) -> Result<locator::Locator, FfiError> {
+    // note: it might seem to make more sense to do all the unsafe operations in the parent
+    // function and leave only the "business logic" here, but doing it here allows us to handle
+    // all errors at the higher level and pass them back across FFI.
+    let path_str = unsafe { CStr::from_ptr(path) };
+    let path_str = path_str
+        .to_str()
+        .map_err(|e| format!("converting path string: {e}"))?;
+
+    // if no path specified, default to first ledger device
+    let path_str = if path_str.is_empty() {
+        "usb://ledger"
+    } else {
+        path_str
+    };
+
+    Ok(locator::Locator::new_from_path(path_str)
+        .map_err(|e| format!("creating locator from path string: {e}"))?)
+}
+
+/// Error returned across FFI, keeping the status word returned by the device, or the status code
+/// of errors callers handle specifically, so that it can be passed back to the caller
+#[derive(Debug)]
+struct FfiError {
+    message: String,
+    code: Option<u16>,
+}
+
+impl FfiError {
+    fn new(context: &str, err: RemoteWalletError) -> Self {
+        let code = match err {
+            RemoteWalletError::AddressMismatch { .. } => Some(ADDRESS_MISMATCH),
+            RemoteWalletError::NetworkMismatch { .. } => Some(NETWORK_MISMATCH),
+            _ => err.status_word(),
+        };
+        Self {
+            message: format!("{context}: {err}"),
+            code,
+        }
+    }
+
+    fn code(&self) -> u16 {
+        self.code.unwrap_or(1)
+    }
+}
+
+impl fmt::Display for FfiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<String> for FfiError {
+    fn from(message: String) -> Self {
+        Self {
+            message,
+            code: None,
+        }
+    }
+}
+
+impl From<&str> for FfiError {
+    fn from(message: &str) -> Self {
+        message.to_string().into()
+    }
 }
 
 #[cfg(test)]
@@ -105,7 +351,8 @@ mod tests {
         let path = DerivationPath::from_absolute_path_str(s)?;
         let wm = &remote_wallet::maybe_wallet_manager().unwrap();
         if let Some(wm) = wm {
-            return match remote_keypair::generate_remote_keypair(locator, path, wm, false, "main") {
+            let selection = remote_wallet::DeviceSelection::FirstMatch;
+            return match remote_keypair::generate_remote_keypair(locator, path, wm, false, "main", &selection) {
                 Ok(kp) => {
                     println!("uri: {}, path: {:?}, pubkey: {}", kp.path, kp.derivation_path, kp.pubkey);
                     Ok(())
@@ -1,5 +1,6 @@
 use {
-    solana_sdk::pubkey::{ParsePubkeyError, Pubkey},
+    crate::ledger_model::{LedgerModel, LedgerModelError},
+    spacemesh_signer::{ParsePubkeyError, Pubkey},
     std::{
         convert::{Infallible, TryFrom, TryInto},
         str::FromStr,
@@ -8,20 +9,58 @@ use {
     uriparse::{URIReference, URIReferenceBuilder, URIReferenceError},
 };
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub enum Manufacturer {
+    #[default]
     Unknown,
     Ledger,
 }
 
-impl Default for Manufacturer {
-    fn default() -> Self {
-        Self::Unknown
+const MANUFACTURER_UNKNOWN: &str = "unknown";
+const MANUFACTURER_LEDGER: &str = "ledger";
+
+/// Known manufacturers and their names in locators. Whether a manufacturer is supported depends
+/// on the drivers registered with the `RemoteWalletManager`.
+const MANUFACTURERS: &[(Manufacturer, &str)] = &[(Manufacturer::Ledger, MANUFACTURER_LEDGER)];
+
+impl Manufacturer {
+    /// Known manufacturers, excluding `Unknown`
+    pub fn all() -> impl Iterator<Item = Manufacturer> {
+        MANUFACTURERS.iter().map(|(manufacturer, _)| *manufacturer)
+    }
+
+    /// Parse the name of one of this manufacturer's device models, e.g. `nano-s-plus` or
+    /// `Nano S Plus` for Ledger. Models can only be named for a known manufacturer.
+    pub fn parse_model(&self, model: &str) -> Result<DeviceModel, LocatorError> {
+        match self {
+            Self::Ledger => Ok(DeviceModel::Ledger(model.parse()?)),
+            Self::Unknown => Err(LocatorError::ManufacturerError(ManufacturerError)),
+        }
     }
 }
 
-const MANUFACTURER_UNKNOWN: &str = "unknown";
-const MANUFACTURER_LEDGER: &str = "ledger";
+/// Device model, by manufacturer
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DeviceModel {
+    Ledger(LedgerModel),
+}
+
+impl DeviceModel {
+    pub fn manufacturer(&self) -> Manufacturer {
+        match self {
+            Self::Ledger(_) => Manufacturer::Ledger,
+        }
+    }
+}
+
+impl std::fmt::Display for DeviceModel {
+    /// Canonical model name, e.g. `nano-s-plus`
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Ledger(model) => write!(f, "{}", model),
+        }
+    }
+}
 
 #[derive(Clone, Debug, Error, PartialEq, Eq)]
 #[error("not a manufacturer")]
@@ -37,10 +76,11 @@ impl FromStr for Manufacturer {
     type Err = ManufacturerError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let s = s.to_ascii_lowercase();
-        match s.as_str() {
-            MANUFACTURER_LEDGER => Ok(Self::Ledger),
-            _ => Err(ManufacturerError),
-        }
+        MANUFACTURERS
+            .iter()
+            .find(|(_, name)| *name == s)
+            .map(|(manufacturer, _)| *manufacturer)
+            .ok_or(ManufacturerError)
     }
 }
 
@@ -53,10 +93,10 @@ impl TryFrom<&str> for Manufacturer {
 
 impl AsRef<str> for Manufacturer {
     fn as_ref(&self) -> &str {
-        match self {
-            Self::Unknown => MANUFACTURER_UNKNOWN,
-            Self::Ledger => MANUFACTURER_LEDGER,
-        }
+        MANUFACTURERS
+            .iter()
+            .find(|(manufacturer, _)| manufacturer == self)
+            .map_or(MANUFACTURER_UNKNOWN, |(_, name)| name)
     }
 }
 
@@ -74,9 +114,13 @@ pub enum LocatorError {
     #[error(transparent)]
     PubkeyError(#[from] ParsePubkeyError),
     #[error(transparent)]
+    LedgerModelError(#[from] LedgerModelError),
+    #[error(transparent)]
     UriReferenceError(#[from] URIReferenceError),
     #[error("unimplemented scheme")]
     UnimplementedScheme,
+    #[error("empty value for device selector `{0}`")]
+    EmptySelector(&'static str),
     #[error("infallible")]
     Infallible,
 }
@@ -87,10 +131,21 @@ impl From<Infallible> for LocatorError {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+const SELECTOR_SERIAL: &str = "serial";
+const SELECTOR_MODEL: &str = "model";
+const SELECTOR_HOST_PATH: &str = "host-path";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct Locator {
     pub manufacturer: Manufacturer,
     pub pubkey: Option<Pubkey>,
+    /// Device serial number, from the `serial` query-string key
+    pub serial: Option<String>,
+    /// Device model, from the `model` query-string key, e.g. `nano-s-plus`, see
+    /// `Manufacturer::parse_model`
+    pub model: Option<DeviceModel>,
+    /// Host device path, from the `host-path` query-string key
+    pub host_device_path: Option<String>,
 }
 
 impl std::fmt::Display for Locator {
@@ -107,6 +162,11 @@ impl std::fmt::Display for Locator {
             .try_path(path)
             .unwrap();
 
+        let query = self.selector_query();
+        if !query.is_empty() {
+            builder.try_query(Some(query.as_str())).unwrap();
+        }
+
         let uri = builder.build().unwrap();
         write!(f, "{}", uri)
     }
@@ -124,14 +184,18 @@ impl Locator {
         let host = uri.host().map(|h| h.to_string());
         match (scheme, host) {
             (Some(scheme), Some(host)) if scheme == "usb" => {
-                let path = uri.path().segments().get(0).and_then(|s| {
+                let path = uri.path().segments().first().and_then(|s| {
                     if !s.is_empty() {
                         Some(s.as_str())
                     } else {
                         None
                     }
                 });
-                Self::new_from_parts(host.as_str(), path)
+                let locator = Self::new_from_parts(host.as_str(), path)?;
+                match uri.query() {
+                    Some(query) => locator.with_selectors(query.as_str()),
+                    None => Ok(locator),
+                }
             }
             (Some(_scheme), Some(_host)) => Err(LocatorError::UnimplementedScheme),
             (None, Some(_host)) => Err(LocatorError::UnimplementedScheme),
@@ -158,8 +222,44 @@ impl Locator {
         Ok(Self {
             manufacturer,
             pubkey,
+            ..Self::default()
         })
     }
+
+    /// Whether the locator narrows the device by identity (serial, model or host path), rather
+    /// than only by manufacturer and pubkey
+    pub fn has_device_selector(&self) -> bool {
+        self.serial.is_some() || self.model.is_some() || self.host_device_path.is_some()
+    }
+
+    // Other query-string keys, such as the derivation path `key`, are left to their own parsers
+    fn with_selectors(mut self, query: &str) -> Result<Self, LocatorError> {
+        let query = qstring::QString::from(query);
+        let selector = |name: &'static str| match query.get(name) {
+            Some("") => Err(LocatorError::EmptySelector(name)),
+            value => Ok(value.map(str::to_string)),
+        };
+        self.serial = selector(SELECTOR_SERIAL)?;
+        self.model = selector(SELECTOR_MODEL)?
+            .map(|model| self.manufacturer.parse_model(&model))
+            .transpose()?;
+        self.host_device_path = selector(SELECTOR_HOST_PATH)?;
+        Ok(self)
+    }
+
+    fn selector_query(&self) -> String {
+        let model = self.model.map(|model| model.to_string());
+        let selectors = [
+            (SELECTOR_SERIAL, &self.serial),
+            (SELECTOR_MODEL, &model),
+            (SELECTOR_HOST_PATH, &self.host_device_path),
+        ];
+        let pairs: Vec<(&str, &str)> = selectors
+            .iter()
+            .filter_map(|(name, value)| value.as_deref().map(|value| (*name, value)))
+            .collect();
+        qstring::QString::new(pairs).to_string()
+    }
 }
 
 #[cfg(test)]
@@ -177,6 +277,10 @@ mod tests {
         assert!(
             matches!(Manufacturer::from_str("bad-manufacturer"), Err(e) if e == ManufacturerError)
         );
+        for manufacturer in Manufacturer::all() {
+            assert_eq!(manufacturer.as_ref().parse(), Ok(manufacturer));
+        }
+        assert_eq!(Manufacturer::Unknown.as_ref(), MANUFACTURER_UNKNOWN);
     }
 
     #[test]
@@ -189,6 +293,7 @@ mod tests {
         let expect = Locator {
             manufacturer,
             pubkey: None,
+            ..Locator::default()
         };
         assert!(matches!(
             Locator::new_from_parts(manufacturer, None::<Pubkey>),
@@ -202,6 +307,7 @@ mod tests {
         let expect = Locator {
             manufacturer,
             pubkey: Some(pubkey),
+            ..Locator::default()
         };
         assert!(matches!(
             Locator::new_from_parts(manufacturer, Some(pubkey)),
@@ -243,6 +349,7 @@ mod tests {
         let expect = Locator {
             manufacturer,
             pubkey: Some(pubkey),
+            ..Locator::default()
         };
         assert_eq!(Locator::new_from_uri(&uri), Ok(expect));
 
@@ -259,6 +366,7 @@ mod tests {
         let expect = Locator {
             manufacturer,
             pubkey: Some(pubkey),
+            ..Locator::default()
         };
         assert_eq!(Locator::new_from_uri(&uri), Ok(expect));
 
@@ -275,6 +383,7 @@ mod tests {
         let expect = Locator {
             manufacturer,
             pubkey: None,
+            ..Locator::default()
         };
         assert_eq!(Locator::new_from_uri(&uri), Ok(expect));
 
@@ -291,6 +400,7 @@ mod tests {
         let expect = Locator {
             manufacturer,
             pubkey: None,
+            ..Locator::default()
         };
         assert_eq!(Locator::new_from_uri(&uri), Ok(expect));
 
@@ -352,6 +462,7 @@ mod tests {
         let expect = Locator {
             manufacturer,
             pubkey: Some(pubkey),
+            ..Locator::default()
         };
         assert_eq!(Locator::new_from_path(path), Ok(expect));
 
@@ -360,6 +471,7 @@ mod tests {
         let expect = Locator {
             manufacturer,
             pubkey: Some(pubkey),
+            ..Locator::default()
         };
         assert_eq!(Locator::new_from_path(path), Ok(expect));
 
@@ -368,6 +480,7 @@ mod tests {
         let expect = Locator {
             manufacturer,
             pubkey: None,
+            ..Locator::default()
         };
         assert_eq!(Locator::new_from_path(path), Ok(expect));
 
@@ -376,6 +489,7 @@ mod tests {
         let expect = Locator {
             manufacturer,
             pubkey: None,
+            ..Locator::default()
         };
         assert_eq!(Locator::new_from_path(path), Ok(expect));
 
@@ -400,4 +514,49 @@ mod tests {
             Err(LocatorError::PubkeyError(ParsePubkeyError::Invalid))
         );
     }
+
+    #[test]
+    fn test_locator_selectors() {
+        let pubkey = Pubkey::new_unique();
+
+        // usb://ledger/{PUBKEY}?key=0/0&serial=0001
+        let path = format!("usb://ledger/{}?key=0/0&serial=0001", pubkey);
+        let expect = Locator {
+            manufacturer: Manufacturer::Ledger,
+            pubkey: Some(pubkey),
+            serial: Some("0001".to_string()),
+            ..Locator::default()
+        };
+        assert_eq!(Locator::new_from_path(path), Ok(expect));
+
+        // usb://ledger?model=nano-x&host-path=%2Fdev%2Fhidraw0
+        let path = "usb://ledger?model=nano-x&host-path=%2Fdev%2Fhidraw0";
+        let expect = Locator {
+            manufacturer: Manufacturer::Ledger,
+            model: Some(DeviceModel::Ledger(LedgerModel::NanoX)),
+            host_device_path: Some("/dev/hidraw0".to_string()),
+            ..Locator::default()
+        };
+        let locator = Locator::new_from_path(path).unwrap();
+        assert!(locator.has_device_selector());
+        assert_eq!(locator, expect);
+        assert_eq!(Locator::new_from_path(locator.to_string()), Ok(expect));
+
+        // usb://ledger?serial=
+        assert_eq!(
+            Locator::new_from_path("usb://ledger?serial="),
+            Err(LocatorError::EmptySelector(SELECTOR_SERIAL))
+        );
+        assert!(matches!(
+            Locator::new_from_path("usb://ledger?model=nano-z"),
+            Err(LocatorError::LedgerModelError(_))
+        ));
+        assert_eq!(
+            Locator::default().with_selectors("model=nano-x"),
+            Err(LocatorError::ManufacturerError(ManufacturerError))
+        );
+        assert!(!Locator::new_from_path("usb://ledger")
+            .unwrap()
+            .has_device_selector());
+    }
 }
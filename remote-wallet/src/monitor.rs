@@ -0,0 +1,309 @@
+use {
+    crate::remote_wallet::{DeviceScan, RemoteWalletError, RemoteWalletInfo, RemoteWalletManager},
+    log::*,
+    std::{
+        collections::btree_map::Entry,
+        sync::{
+            mpsc::{self, Receiver, RecvTimeoutError, Sender},
+            Arc,
+        },
+        thread::{self, JoinHandle},
+        time::Duration,
+    },
+};
+
+/// Default interval between two enumerations of connected devices. Enumerating doesn't talk to
+/// the devices, only the devices that were connected or reopened since the last one, or whose
+/// last probe failed, are probed.
+pub const DEFAULT_MONITOR_INTERVAL: Duration = Duration::from_millis(1000);
+
+/// Change in the set of connected devices, as observed by a `DeviceMonitor`
+#[derive(Debug, Clone)]
+pub enum DeviceEvent {
+    /// A device was plugged in
+    Connected { host_device_path: String },
+    /// A device was unplugged
+    Disconnected { host_device_path: String },
+    /// The Spacemesh app is running and responding on a connected device
+    AppOpened(RemoteWalletInfo),
+    /// The Spacemesh app was closed on a device that is still connected
+    AppClosed { host_device_path: String },
+    /// Enumeration failed, or a connected device could not be read for a reason other than the
+    /// app not being open
+    Error {
+        host_device_path: Option<String>,
+        error: RemoteWalletError,
+    },
+}
+
+/// Handle to a background thread watching for connected devices. The thread is stopped when the
+/// handle is dropped.
+pub struct DeviceMonitor {
+    stop: Option<Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl DeviceMonitor {
+    /// Start watching `wallet_manager`, enumerating devices every `interval` and passing events to
+    /// `on_event`. Devices are probed when they are connected, and when they are reopened because
+    /// their product ID changed, e.g. as the Spacemesh app was opened or closed. Devices whose last
+    /// probe failed, e.g. because they were locked, are probed again at every enumeration. The
+    /// device list of `wallet_manager` is kept current while monitoring.
+    pub fn start<F>(
+        wallet_manager: Arc<RemoteWalletManager>,
+        interval: Duration,
+        on_event: F,
+    ) -> Self
+    where
+        F: Fn(DeviceEvent) + Send + 'static,
+    {
+        let (stop, stopped) = mpsc::channel();
+        let handle = thread::spawn(move || {
+            let mut known = DeviceScan::new();
+            loop {
+                match wallet_manager.open_devices() {
+                    Ok(opened) => {
+                        let probed = wallet_manager
+                            .probe_known_devices(|path| needs_probe(&known, &opened, path));
+                        let scan = carry_over(&known, wallet_manager.device_paths(), probed);
+                        diff_scans(&known, &scan).into_iter().for_each(&on_event);
+                        known = scan;
+                    }
+                    Err(error) => on_event(DeviceEvent::Error {
+                        host_device_path: None,
+                        error,
+                    }),
+                }
+                match stopped.recv_timeout(interval) {
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    Ok(()) | Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+            trace!("Device monitor stopped");
+        });
+        Self {
+            stop: Some(stop),
+            handle: Some(handle),
+        }
+    }
+
+    /// Start watching `wallet_manager` like `start`, delivering events on a channel
+    pub fn start_with_channel(
+        wallet_manager: Arc<RemoteWalletManager>,
+        interval: Duration,
+    ) -> (Self, Receiver<DeviceEvent>) {
+        let (sender, receiver) = mpsc::channel();
+        let monitor = Self::start(wallet_manager, interval, move |event| {
+            // the receiver going away just means nobody is listening anymore
+            let _ = sender.send(event);
+        });
+        (monitor, receiver)
+    }
+
+    /// Stop watching and wait for the background thread to exit
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        drop(self.stop.take());
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                error!("Device monitor thread panicked");
+            }
+        }
+    }
+}
+
+impl Drop for DeviceMonitor {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Whether the device at `path` must be probed: it was just `opened`, it is new, or it could not
+/// be read last time
+fn needs_probe(known: &DeviceScan, opened: &[String], path: &str) -> bool {
+    opened.iter().any(|opened| opened == path) || !matches!(known.get(path), Some(Ok(_)))
+}
+
+/// State of the devices at `paths`: the outcome of probing them if they were `probed`, or else
+/// their `known` state
+fn carry_over(known: &DeviceScan, paths: Vec<String>, mut probed: DeviceScan) -> DeviceScan {
+    for path in paths {
+        if let (Some(state), Entry::Vacant(entry)) = (known.get(&path), probed.entry(path.clone()))
+        {
+            entry.insert(state.clone());
+        }
+    }
+    probed
+}
+
+/// Events describing the transition from `previous` to `current`
+fn diff_scans(previous: &DeviceScan, current: &DeviceScan) -> Vec<DeviceEvent> {
+    let mut events = vec![];
+    for host_device_path in previous.keys() {
+        if !current.contains_key(host_device_path) {
+            events.push(DeviceEvent::Disconnected {
+                host_device_path: host_device_path.clone(),
+            });
+        }
+    }
+    for (host_device_path, state) in current {
+        let previous_state = previous.get(host_device_path);
+        if previous_state.is_none() {
+            events.push(DeviceEvent::Connected {
+                host_device_path: host_device_path.clone(),
+            });
+        }
+        match (previous_state, state) {
+            (Some(Ok(previous_info)), Ok(info)) if previous_info.pubkey == info.pubkey => {}
+            (_, Ok(info)) => events.push(DeviceEvent::AppOpened(info.clone())),
+            (Some(Ok(_)), Err(error)) if error.is_app_not_open() => {
+                events.push(DeviceEvent::AppClosed {
+                    host_device_path: host_device_path.clone(),
+                })
+            }
+            (Some(Err(previous_error)), Err(error))
+                if previous_error.to_string() == error.to_string() => {}
+            (_, Err(error)) if error.is_app_not_open() => {}
+            (_, Err(error)) => events.push(DeviceEvent::Error {
+                host_device_path: Some(host_device_path.clone()),
+                error: error.clone(),
+            }),
+        }
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::ledger_error::LedgerError, spacemesh_signer::Pubkey};
+
+    fn info(host_device_path: &str, pubkey: Pubkey) -> RemoteWalletInfo {
+        RemoteWalletInfo {
+            host_device_path: host_device_path.to_string(),
+            pubkey,
+            ..RemoteWalletInfo::default()
+        }
+    }
+
+    fn describe(events: Vec<DeviceEvent>) -> Vec<String> {
+        events
+            .into_iter()
+            .map(|event| match event {
+                DeviceEvent::Connected { host_device_path } => {
+                    format!("connected {host_device_path}")
+                }
+                DeviceEvent::Disconnected { host_device_path } => {
+                    format!("disconnected {host_device_path}")
+                }
+                DeviceEvent::AppOpened(info) => format!("opened {}", info.host_device_path),
+                DeviceEvent::AppClosed { host_device_path } => format!("closed {host_device_path}"),
+                DeviceEvent::Error {
+                    host_device_path,
+                    error,
+                } => format!("error {host_device_path:?} {error}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_scans() {
+        let pubkey = Pubkey::new_unique();
+        let app_closed = || Err(LedgerError::AppNotOpen.into());
+
+        let empty = DeviceScan::new();
+        let plugged: DeviceScan = [("/dev/a".to_string(), app_closed())].into();
+        assert_eq!(
+            describe(diff_scans(&empty, &plugged)),
+            vec!["connected /dev/a"]
+        );
+
+        let opened: DeviceScan = [("/dev/a".to_string(), Ok(info("/dev/a", pubkey)))].into();
+        assert_eq!(
+            describe(diff_scans(&plugged, &opened)),
+            vec!["opened /dev/a"]
+        );
+        assert!(diff_scans(&opened, &opened).is_empty());
+        assert_eq!(
+            describe(diff_scans(&opened, &plugged)),
+            vec!["closed /dev/a"]
+        );
+        assert_eq!(
+            describe(diff_scans(&opened, &empty)),
+            vec!["disconnected /dev/a"]
+        );
+        assert_eq!(
+            describe(diff_scans(&empty, &opened)),
+            vec!["connected /dev/a", "opened /dev/a"]
+        );
+
+        let locked: DeviceScan =
+            [("/dev/a".to_string(), Err(LedgerError::DeviceLocked.into()))].into();
+        assert_eq!(
+            describe(diff_scans(&opened, &locked)),
+            vec![format!(
+                "error Some(\"/dev/a\") {}",
+                LedgerError::DeviceLocked
+            )]
+        );
+        assert!(diff_scans(&locked, &locked).is_empty());
+    }
+
+    #[test]
+    fn test_carry_over() {
+        let known: DeviceScan = [
+            (
+                "/dev/a".to_string(),
+                Ok(info("/dev/a", Pubkey::new_unique())),
+            ),
+            ("/dev/b".to_string(), Err(LedgerError::AppNotOpen.into())),
+        ]
+        .into();
+        let probed: DeviceScan = [
+            (
+                "/dev/b".to_string(),
+                Ok(info("/dev/b", Pubkey::new_unique())),
+            ),
+            ("/dev/c".to_string(), Err(LedgerError::AppNotOpen.into())),
+        ]
+        .into();
+        let paths = ["/dev/a", "/dev/b", "/dev/c"].map(str::to_string).to_vec();
+        let scan = carry_over(&known, paths, probed);
+        assert_eq!(scan.len(), 3);
+        assert_eq!(
+            scan["/dev/a"].as_ref().unwrap().pubkey,
+            known["/dev/a"].as_ref().unwrap().pubkey
+        );
+        assert!(scan["/dev/b"].is_ok());
+        assert!(scan["/dev/c"].is_err());
+        assert_eq!(
+            describe(diff_scans(&known, &scan)),
+            vec!["opened /dev/b", "connected /dev/c"]
+        );
+
+        // a device that was locked at its first probe is probed again, and opens once unlocked
+        let locked: DeviceScan =
+            [("/dev/a".to_string(), Err(LedgerError::DeviceLocked.into()))].into();
+        let opened = Vec::new();
+        assert!(needs_probe(&locked, &opened, "/dev/a"));
+        assert!(!needs_probe(&known, &opened, "/dev/a"));
+        assert!(needs_probe(&known, &opened, "/dev/b"));
+        assert!(needs_probe(&known, &["/dev/a".to_string()], "/dev/a"));
+        let probed: DeviceScan = [(
+            "/dev/a".to_string(),
+            Ok(info("/dev/a", Pubkey::new_unique())),
+        )]
+        .into();
+        let scan = carry_over(&locked, vec!["/dev/a".to_string()], probed);
+        assert_eq!(describe(diff_scans(&locked, &scan)), vec!["opened /dev/a"]);
+
+        // unplugged devices are dropped
+        let scan = carry_over(&known, vec!["/dev/b".to_string()], DeviceScan::new());
+        assert_eq!(
+            describe(diff_scans(&known, &scan)),
+            vec!["disconnected /dev/a"]
+        );
+    }
+}
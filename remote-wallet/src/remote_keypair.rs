@@ -1,44 +1,140 @@
 use {
     crate::{
-        ledger::get_ledger_from_info,
-        locator::{Locator, Manufacturer},
+        locator::Locator,
         remote_wallet::{
-            RemoteWallet, RemoteWalletError, RemoteWalletInfo, RemoteWalletManager,
-            RemoteWalletType,
+            DeviceSelection, RemoteWallet, RemoteWalletError, RemoteWalletInfo, RemoteWalletManager,
         },
     },
-    solana_sdk::{
-        pubkey::Pubkey,
-        signature::{Signature, Signer, SignerError},
-    },
     spacemesh_derivation_path::DerivationPath,
+    spacemesh_signer::{OffchainMessage, Pubkey, Signature, Signer, SignerError},
+    spacemesh_transaction::{wallet, Address},
+    std::sync::Arc,
 };
 
+/// Kind of payload sent to a remote wallet for signing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadType {
+    /// A transaction the device can parse and display to the user
+    Transaction,
+    /// Arbitrary bytes the device cannot interpret, which it only signs with blind signing enabled
+    Arbitrary,
+}
+
+impl PayloadType {
+    pub fn requires_blind_signing(&self) -> bool {
+        matches!(self, Self::Arbitrary)
+    }
+}
+
+/// Signer backed by a key on a remote wallet. It is `Send` and `Sync`, so it can be shared between
+/// threads; concurrent requests to the same device are queued.
 pub struct RemoteKeypair {
-    pub wallet_type: RemoteWalletType,
+    pub wallet: Arc<dyn RemoteWallet>,
     pub derivation_path: DerivationPath,
     pub pubkey: Pubkey,
     pub path: String,
+    /// Check each signature returned by the device against `pubkey` and the signed payload.
+    /// Enabled by default; disabling it only saves a signature verification per request.
+    pub verify_signatures: bool,
 }
 
 impl RemoteKeypair {
     pub fn new(
-        wallet_type: RemoteWalletType,
+        wallet: Arc<dyn RemoteWallet>,
         derivation_path: DerivationPath,
         confirm_key: bool,
         path: String,
     ) -> Result<Self, RemoteWalletError> {
-        let pubkey = match &wallet_type {
-            RemoteWalletType::Ledger(wallet) => wallet.get_pubkey(&derivation_path, confirm_key)?,
-        };
+        if confirm_key && !wallet.can_confirm_keys() {
+            return Err(RemoteWalletError::InvalidInput(format!(
+                "{} cannot confirm keys",
+                wallet.name()
+            )));
+        }
+        let pubkey = wallet.get_pubkey(&derivation_path, confirm_key)?;
 
         Ok(Self {
-            wallet_type,
+            wallet,
             derivation_path,
             pubkey,
             path,
+            verify_signatures: true,
         })
     }
+
+    /// Sign `payload`. Payloads that require blind signing are checked against the device
+    /// settings first, so that users get an actionable error instead of a device rejection. The
+    /// signature is checked against `pubkey` unless `verify_signatures` is disabled, so that a
+    /// faulty device or transport, or a swapped device, is caught before the signature is used.
+    pub fn sign_payload(
+        &self,
+        payload: &[u8],
+        payload_type: PayloadType,
+    ) -> Result<Signature, RemoteWalletError> {
+        if payload_type.requires_blind_signing() {
+            if let Some(app) = self.wallet.app() {
+                if !app.is_blind_signing_enabled()? {
+                    return Err(RemoteWalletError::BlindSigningDisabled);
+                }
+            }
+        }
+        let signature = self.wallet.sign_message(&self.derivation_path, payload)?;
+        if self.verify_signatures && !signature.verify(&self.pubkey, payload) {
+            return Err(RemoteWalletError::InvalidSignature);
+        }
+        Ok(signature)
+    }
+
+    /// Sign `message` as an off-chain message, which can't be replayed as a transaction. Devices
+    /// can't parse it, so it requires blind signing.
+    pub fn sign_offchain_message(
+        &self,
+        message: &OffchainMessage,
+    ) -> Result<Signature, RemoteWalletError> {
+        self.sign_payload(&message.signing_body(), PayloadType::Arbitrary)
+    }
+
+    /// Have the device display the address of the wallet account controlled by this key, and
+    /// check the key it was computed from, and the address itself if the device reports it,
+    /// against the address computed on the host from `pubkey`, encoded with `hrp`. The user should
+    /// only trust the address once they've compared the two. Returns the address.
+    pub fn verify_address(&self, hrp: &str) -> Result<String, RemoteWalletError> {
+        if !self.wallet.can_display_addresses() {
+            return Err(RemoteWalletError::InvalidInput(format!(
+                "{} cannot display addresses",
+                self.wallet.name()
+            )));
+        }
+        let address = wallet::principal(&self.pubkey);
+        let expected = address
+            .encode(hrp)
+            .map_err(|err| RemoteWalletError::InvalidInput(err.to_string()))?;
+        let (pubkey, device) = self.wallet.display_address(&self.derivation_path)?;
+        let device = match device {
+            Some(device) => {
+                let (device_hrp, device_address) = Address::decode(&device).map_err(|_| {
+                    RemoteWalletError::Protocol("Device returned an invalid address")
+                })?;
+                if device_hrp != hrp {
+                    return Err(RemoteWalletError::NetworkMismatch {
+                        device: device_hrp,
+                        expected: hrp.to_string(),
+                    });
+                }
+                if device_address != address {
+                    return Err(RemoteWalletError::AddressMismatch { device, expected });
+                }
+                device
+            }
+            None => wallet::principal(&pubkey)
+                .encode(hrp)
+                .map_err(|err| RemoteWalletError::InvalidInput(err.to_string()))?,
+        };
+        if pubkey != self.pubkey {
+            return Err(RemoteWalletError::AddressMismatch { device, expected });
+        }
+        Ok(expected)
+    }
 }
 
 impl Signer for RemoteKeypair {
@@ -47,11 +143,8 @@ impl Signer for RemoteKeypair {
     }
 
     fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
-        match &self.wallet_type {
-            RemoteWalletType::Ledger(wallet) => wallet
-                .sign_message(&self.derivation_path, message)
-                .map_err(|e| e.into()),
-        }
+        self.sign_payload(message, PayloadType::Transaction)
+            .map_err(|e| e.into())
     }
 
     fn is_interactive(&self) -> bool {
@@ -59,24 +152,320 @@ impl Signer for RemoteKeypair {
     }
 }
 
+#[cfg(feature = "solana")]
+impl solana_sdk::signer::Signer for RemoteKeypair {
+    fn try_pubkey(&self) -> Result<solana_sdk::pubkey::Pubkey, solana_sdk::signer::SignerError> {
+        Ok(self.pubkey.into())
+    }
+
+    fn try_sign_message(
+        &self,
+        message: &[u8],
+    ) -> Result<solana_sdk::signature::Signature, solana_sdk::signer::SignerError> {
+        Signer::try_sign_message(self, message)
+            .map(Into::into)
+            .map_err(Into::into)
+    }
+
+    fn is_interactive(&self) -> bool {
+        Signer::is_interactive(self)
+    }
+}
+
+/// Build a `RemoteKeypair` for the device matching `locator`. If several devices match,
+/// `selection` decides which one is used.
 pub fn generate_remote_keypair(
     locator: Locator,
     derivation_path: DerivationPath,
     wallet_manager: &RemoteWalletManager,
     confirm_key: bool,
     keypair_name: &str,
+    selection: &DeviceSelection,
 ) -> Result<RemoteKeypair, RemoteWalletError> {
     let remote_wallet_info = RemoteWalletInfo::parse_locator(locator);
-    if remote_wallet_info.manufacturer == Manufacturer::Ledger {
-        let ledger = get_ledger_from_info(remote_wallet_info, keypair_name, wallet_manager)?;
-        let path = format!("{}{}", ledger.pretty_path, derivation_path.get_query());
-        Ok(RemoteKeypair::new(
-            RemoteWalletType::Ledger(ledger),
-            derivation_path,
-            confirm_key,
-            path,
-        )?)
-    } else {
-        Err(RemoteWalletError::DeviceTypeMismatch)
+    if wallet_manager
+        .registry()
+        .get(remote_wallet_info.manufacturer)
+        .is_none()
+    {
+        return Err(RemoteWalletError::DeviceTypeMismatch);
+    }
+    let wallet =
+        wallet_manager.get_wallet_from_info(remote_wallet_info, keypair_name, selection)?;
+    if let Some(app) = wallet.app() {
+        app.check_app_version()?;
+    }
+    let path = format!("{}{}", wallet.pretty_path(), derivation_path.get_query());
+    RemoteKeypair::new(wallet, derivation_path, confirm_key, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{locator::Manufacturer, remote_wallet::DeviceApp},
+        spacemesh_signer::SoftwareKeypair,
+        spacemesh_transaction::{
+            multisig::{self, MultisigTransaction, SpawnArguments},
+            Payload, TransactionError, MAINNET_HRP, TESTNET_HRP,
+        },
+        std::{
+            any::Any,
+            sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+        },
+    };
+
+    /// Wallet signing with a software key, optionally corrupting its signatures and addresses
+    #[derive(Debug)]
+    struct SoftwareWallet {
+        keypair: SoftwareKeypair,
+        corrupt: AtomicBool,
+        blind_signing: AtomicBool,
+        sign_calls: AtomicUsize,
+    }
+
+    impl SoftwareWallet {
+        fn new() -> Arc<Self> {
+            Arc::new(Self {
+                keypair: SoftwareKeypair::from_seed(&[6u8; 64], DerivationPath::default()).unwrap(),
+                corrupt: AtomicBool::new(false),
+                blind_signing: AtomicBool::new(true),
+                sign_calls: AtomicUsize::new(0),
+            })
+        }
+    }
+
+    impl RemoteWallet for SoftwareWallet {
+        fn name(&self) -> &str {
+            "software wallet"
+        }
+
+        fn manufacturer(&self) -> Manufacturer {
+            Manufacturer::Unknown
+        }
+
+        fn pretty_path(&self) -> String {
+            "usb://unknown".to_string()
+        }
+
+        fn can_confirm_keys(&self) -> bool {
+            false
+        }
+
+        fn can_display_addresses(&self) -> bool {
+            true
+        }
+
+        fn max_message_len(&self) -> usize {
+            usize::MAX
+        }
+
+        fn get_pubkey(
+            &self,
+            _derivation_path: &DerivationPath,
+            _confirm_key: bool,
+        ) -> Result<Pubkey, RemoteWalletError> {
+            Ok(self.keypair.pubkey())
+        }
+
+        fn display_address(
+            &self,
+            _derivation_path: &DerivationPath,
+        ) -> Result<(Pubkey, Option<String>), RemoteWalletError> {
+            let pubkey = self.keypair.pubkey();
+            let address = if self.corrupt.load(Ordering::Relaxed) {
+                wallet::principal(&Pubkey::new_unique())
+            } else {
+                wallet::principal(&pubkey)
+            };
+            let address = address
+                .encode(MAINNET_HRP)
+                .map_err(|err| RemoteWalletError::InvalidInput(err.to_string()))?;
+            Ok((pubkey, Some(address)))
+        }
+
+        fn sign_message(
+            &self,
+            _derivation_path: &DerivationPath,
+            data: &[u8],
+        ) -> Result<Signature, RemoteWalletError> {
+            self.sign_calls.fetch_add(1, Ordering::Relaxed);
+            let mut signature = self.keypair.sign_message(data).to_bytes();
+            if self.corrupt.load(Ordering::Relaxed) {
+                signature[0] ^= 1;
+            }
+            Ok(signature.into())
+        }
+
+        fn get_base_pubkey(&self) -> Result<Pubkey, RemoteWalletError> {
+            Ok(self.keypair.pubkey())
+        }
+
+        fn probe(&self) -> Result<(), RemoteWalletError> {
+            Ok(())
+        }
+
+        fn app(&self) -> Option<&dyn DeviceApp> {
+            Some(self)
+        }
+
+        fn fill_info(&self, info: RemoteWalletInfo) -> RemoteWalletInfo {
+            info
+        }
+
+        fn into_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync> {
+            self
+        }
+    }
+
+    impl DeviceApp for SoftwareWallet {
+        fn is_blind_signing_enabled(&self) -> Result<bool, RemoteWalletError> {
+            Ok(self.blind_signing.load(Ordering::Relaxed))
+        }
+
+        fn check_app_version(&self) -> Result<(), RemoteWalletError> {
+            Ok(())
+        }
+    }
+
+    fn keypair(wallet: &Arc<SoftwareWallet>) -> RemoteKeypair {
+        RemoteKeypair::new(
+            wallet.clone(),
+            DerivationPath::default(),
+            false,
+            wallet.pretty_path(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_confirm_key_requires_capability() {
+        let wallet = SoftwareWallet::new();
+        assert!(!wallet.can_confirm_keys());
+        assert!(matches!(
+            RemoteKeypair::new(
+                wallet.clone(),
+                DerivationPath::default(),
+                true,
+                wallet.pretty_path()
+            ),
+            Err(RemoteWalletError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_signatures() {
+        let wallet = SoftwareWallet::new();
+        let mut keypair = keypair(&wallet);
+        assert!(keypair.verify_signatures);
+
+        let signature = keypair.try_sign_message(b"message").unwrap();
+        assert!(signature.verify(&keypair.pubkey, b"message"));
+
+        wallet.corrupt.store(true, Ordering::Relaxed);
+        assert_eq!(
+            keypair.try_sign_message(b"message"),
+            Err(SignerError::InvalidSignature)
+        );
+        assert!(matches!(
+            keypair.sign_offchain_message(&OffchainMessage::new(b"message").unwrap()),
+            Err(RemoteWalletError::InvalidSignature)
+        ));
+
+        keypair.verify_signatures = false;
+        let signature = keypair.try_sign_message(b"message").unwrap();
+        assert!(!signature.verify(&keypair.pubkey, b"message"));
+    }
+
+    #[test]
+    fn test_sign_multisig() {
+        let wallet = SoftwareWallet::new();
+        let keypair = keypair(&wallet);
+        let keys = SpawnArguments::new(1, vec![Pubkey::new_unique(), keypair.pubkey]).unwrap();
+        let tx = multisig::self_spawn(&keys, &Payload::default());
+        let mut multisig = MultisigTransaction::new(tx, [1u8; 20], keys);
+
+        assert_eq!(
+            multisig.sign(0, &keypair),
+            Err(TransactionError::SignerKeyMismatch(0))
+        );
+        wallet.corrupt.store(true, Ordering::Relaxed);
+        assert_eq!(
+            multisig.sign(1, &keypair),
+            Err(TransactionError::SignerError(SignerError::InvalidSignature))
+        );
+        wallet.corrupt.store(false, Ordering::Relaxed);
+        multisig.sign(1, &keypair).unwrap();
+        assert!(multisig.finalize().is_ok());
+    }
+
+    #[test]
+    fn test_verify_address() {
+        let wallet = SoftwareWallet::new();
+        let keypair = keypair(&wallet);
+        let expected = wallet::principal(&keypair.pubkey)
+            .encode(MAINNET_HRP)
+            .unwrap();
+        assert_eq!(keypair.verify_address(MAINNET_HRP).unwrap(), expected);
+
+        // the device encodes mainnet addresses
+        match keypair.verify_address(TESTNET_HRP) {
+            Err(RemoteWalletError::NetworkMismatch { device, expected }) => {
+                assert_eq!(device, MAINNET_HRP);
+                assert_eq!(expected, TESTNET_HRP);
+            }
+            result => panic!("unexpected result {result:?}"),
+        }
+
+        wallet.corrupt.store(true, Ordering::Relaxed);
+        match keypair.verify_address(MAINNET_HRP) {
+            Err(RemoteWalletError::AddressMismatch {
+                device,
+                expected: e,
+            }) => {
+                assert_ne!(device, expected);
+                assert_eq!(e, expected);
+            }
+            result => panic!("unexpected result {result:?}"),
+        }
+    }
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_remote_keypair_is_send_sync() {
+        assert_send_sync::<RemoteKeypair>();
+        assert_send_sync::<std::sync::Arc<RemoteKeypair>>();
+    }
+
+    #[test]
+    fn test_payload_type_requires_blind_signing() {
+        assert!(!PayloadType::Transaction.requires_blind_signing());
+        assert!(PayloadType::Arbitrary.requires_blind_signing());
+    }
+
+    #[test]
+    fn test_blind_signing_disabled() {
+        let wallet = SoftwareWallet::new();
+        let keypair = keypair(&wallet);
+        wallet.blind_signing.store(false, Ordering::Relaxed);
+
+        assert!(matches!(
+            keypair.sign_payload(b"message", PayloadType::Arbitrary),
+            Err(RemoteWalletError::BlindSigningDisabled)
+        ));
+        // rejected before reaching the device
+        assert_eq!(wallet.sign_calls.load(Ordering::Relaxed), 0);
+
+        keypair
+            .sign_payload(b"transaction", PayloadType::Transaction)
+            .unwrap();
+        assert_eq!(wallet.sign_calls.load(Ordering::Relaxed), 1);
+
+        wallet.blind_signing.store(true, Ordering::Relaxed);
+        keypair
+            .sign_payload(b"message", PayloadType::Arbitrary)
+            .unwrap();
+        assert_eq!(wallet.sign_calls.load(Ordering::Relaxed), 2);
     }
 }
@@ -1,20 +1,25 @@
 #[cfg(feature = "hidapi")]
-use {crate::ledger::is_valid_ledger, parking_lot::Mutex};
+use parking_lot::Mutex;
 use {
     crate::{
-        ledger::LedgerWallet,
+        ledger::{LedgerDriver, LedgerSettings, LedgerWallet, RunningApp},
         ledger_error::LedgerError,
-        locator::{Locator, LocatorError, Manufacturer},
+        locator::{DeviceModel, Locator, LocatorError, Manufacturer},
     },
+    dialoguer::{theme::ColorfulTheme, Select},
     log::*,
     parking_lot::RwLock,
-    solana_sdk::{
-        pubkey::Pubkey,
-        signature::{Signature, SignerError},
-    },
+    semver::Version as FirmwareVersion,
     spacemesh_derivation_path::{DerivationPath, DerivationPathError},
+    spacemesh_signer::{Pubkey, Signature, SignerError},
     std::{
-        sync::Arc,
+        any::Any,
+        collections::BTreeMap,
+        fmt,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
         time::{Duration, Instant},
     },
     thiserror::Error,
@@ -22,6 +27,8 @@ use {
 
 const HID_GLOBAL_USAGE_PAGE: u16 = 0xFF00;
 const HID_USB_DEVICE_CLASS: u8 = 0;
+const WAIT_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+const WAIT_MAX_BACKOFF: Duration = Duration::from_secs(2);
 
 /// Remote wallet error.
 #[derive(Error, Debug, Clone)]
@@ -50,6 +57,24 @@ pub enum RemoteWalletError {
     #[error("no device found")]
     NoDeviceFound,
 
+    #[error("{0} matching devices found, select one explicitly")]
+    AmbiguousDevice(usize),
+
+    #[error(
+        "blind signing is disabled on the device: open the Spacemesh app, enable \"Blind \
+         signing\" in its settings and retry"
+    )]
+    BlindSigningDisabled,
+
+    #[error("Spacemesh app not open on the device, {0} is running")]
+    AppNotRunning(String),
+
+    #[error("Spacemesh app version {version} is not supported, update it to {minimum} or later")]
+    AppUpdateRequired {
+        version: FirmwareVersion,
+        minimum: FirmwareVersion,
+    },
+
     #[error("protocol error: {0}")]
     Protocol(&'static str),
 
@@ -59,10 +84,38 @@ pub enum RemoteWalletError {
     #[error("remote wallet operation rejected by the user")]
     UserCancel,
 
+    #[error("signature returned by the device does not verify against its pubkey")]
+    InvalidSignature,
+
+    #[error("device displayed address {device}, expected {expected}")]
+    AddressMismatch { device: String, expected: String },
+
+    #[error("device displayed an address for network {device}, expected {expected}")]
+    NetworkMismatch { device: String, expected: String },
+
     #[error(transparent)]
     LocatorError(#[from] LocatorError),
 }
 
+impl RemoteWalletError {
+    /// Whether the error means the Spacemesh app isn't running on an otherwise reachable device
+    pub fn is_app_not_open(&self) -> bool {
+        match self {
+            RemoteWalletError::LedgerError(e) => e.is_app_not_open(),
+            RemoteWalletError::AppNotRunning(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Status word returned by the device, if this error originated from one
+    pub fn status_word(&self) -> Option<u16> {
+        match self {
+            RemoteWalletError::LedgerError(e) => Some(e.status_word()),
+            _ => None,
+        }
+    }
+}
+
 #[cfg(feature = "hidapi")]
 impl From<hidapi::HidError> for RemoteWalletError {
     fn from(err: hidapi::HidError) -> RemoteWalletError {
@@ -77,71 +130,126 @@ impl From<RemoteWalletError> for SignerError {
             RemoteWalletError::DeviceTypeMismatch => SignerError::Connection(err.to_string()),
             RemoteWalletError::InvalidDevice => SignerError::Connection(err.to_string()),
             RemoteWalletError::InvalidInput(input) => SignerError::InvalidInput(input),
-            RemoteWalletError::LedgerError(e) => SignerError::Protocol(e.to_string()),
+            RemoteWalletError::LedgerError(e) => match e {
+                LedgerError::UserCancel | LedgerError::UserRefused => {
+                    SignerError::UserCancel(e.to_string())
+                }
+                LedgerError::DeviceLocked | LedgerError::DeviceHalted => {
+                    SignerError::Connection(e.to_string())
+                }
+                _ if e.is_app_not_open() => SignerError::Connection(e.to_string()),
+                LedgerError::Unknown(_) => SignerError::Protocol(e.to_string()),
+                _ => SignerError::Protocol(format!("{e} (status word 0x{:04x})", e.status_word())),
+            },
             RemoteWalletError::NoDeviceFound => SignerError::NoDeviceFound,
+            RemoteWalletError::AppNotRunning(_) => SignerError::Connection(err.to_string()),
             RemoteWalletError::Protocol(e) => SignerError::Protocol(e.to_string()),
             RemoteWalletError::UserCancel => {
                 SignerError::UserCancel("remote wallet operation rejected by the user".to_string())
             }
+            RemoteWalletError::InvalidSignature => SignerError::InvalidSignature,
             _ => SignerError::Custom(err.to_string()),
         }
     }
 }
 
-/// Collection of connected RemoteWallets
+/// Outcome of reading each connected device, keyed by host device path
+pub(crate) type DeviceScan = BTreeMap<String, Result<RemoteWalletInfo, RemoteWalletError>>;
+
+/// Collection of connected RemoteWallets. It is `Send` and `Sync`, so it can be shared between
+/// threads, e.g. with a `DeviceMonitor`.
 pub struct RemoteWalletManager {
     #[cfg(feature = "hidapi")]
     usb: Arc<Mutex<hidapi::HidApi>>,
+    registry: DriverRegistry,
     devices: RwLock<Vec<Device>>,
 }
 
 impl RemoteWalletManager {
-    /// Create a new instance.
+    /// Create a new instance, supporting the vendors of `DriverRegistry::default()`.
     #[cfg(feature = "hidapi")]
     pub fn new(usb: Arc<Mutex<hidapi::HidApi>>) -> Arc<Self> {
+        Self::with_registry(usb, DriverRegistry::default())
+    }
+
+    /// Create a new instance, supporting the vendors of `registry`.
+    #[cfg(feature = "hidapi")]
+    pub fn with_registry(usb: Arc<Mutex<hidapi::HidApi>>, registry: DriverRegistry) -> Arc<Self> {
         Arc::new(Self {
             usb,
+            registry,
             devices: RwLock::new(Vec::new()),
         })
     }
 
-    /// Repopulate device list
-    /// Note: this method iterates over and updates all devices
-    #[cfg(feature = "hidapi")]
+    /// Vendor drivers this manager dispatches to
+    pub fn registry(&self) -> &DriverRegistry {
+        &self.registry
+    }
+
+    /// Repopulate device list from HID metadata, without talking to the devices. Values read from
+    /// a device, such as its base pubkey, are fetched on first use and kept for as long as the
+    /// device stays connected. Returns the number of newly detected devices.
     pub fn update_devices(&self) -> Result<usize, RemoteWalletError> {
+        let num_prev_devices = self.devices.read().len();
+        self.open_devices()?;
+        Ok(self.devices.read().len().saturating_sub(num_prev_devices))
+    }
+
+    /// Repopulate device list, opening the devices that weren't known yet. A known device is
+    /// reopened if its product ID or serial number changed, e.g. because another app was
+    /// started, or if talking to it failed at the transport level. Returns the host device paths
+    /// of the devices opened.
+    #[cfg(feature = "hidapi")]
+    pub(crate) fn open_devices(&self) -> Result<Vec<String>, RemoteWalletError> {
+        // holding `usb` keeps other updates out, so the device list itself is only locked to be
+        // read and replaced: drivers may talk to a device while opening it
         let mut usb = self.usb.lock();
         usb.refresh_devices()?;
-        let devices = usb.device_list();
-        let num_prev_devices = self.devices.read().len();
+        let mut known_devices = self.devices.read().clone();
 
         let mut detected_devices = vec![];
+        let mut opened = vec![];
         let mut errors = vec![];
-        for device_info in devices.filter(|&device_info| {
-            is_valid_hid_device(device_info.usage_page(), device_info.interface_number())
-                && is_valid_ledger(device_info.vendor_id(), device_info.product_id())
-        }) {
-            match usb.open_path(device_info.path()) {
-                Ok(device) => {
-                    let mut ledger = LedgerWallet::new(device);
-                    let result = ledger.read_device(device_info);
-                    match result {
-                        Ok(info) => {
-                            ledger.pretty_path = info.get_pretty_path();
-                            let path = device_info.path().to_str().unwrap().to_string();
-                            trace!("Found device: {:?}", info);
-                            detected_devices.push(Device {
-                                path,
-                                info,
-                                wallet_type: RemoteWalletType::Ledger(Arc::new(ledger)),
-                            })
-                        }
-                        Err(err) => {
-                            error!("Error connecting to ledger device to read info: {}", err);
-                            errors.push(err)
-                        }
-                    }
+        for device_info in usb.device_list() {
+            let Some(driver) = self
+                .registry
+                .drivers()
+                .find(|driver| driver.is_supported(device_info))
+            else {
+                continue;
+            };
+            let path = device_info.path().to_string_lossy().to_string();
+            let product_id = device_info.product_id();
+            let serial = device_info.serial_number().map(str::to_string);
+            if let Some(index) = known_devices
+                .iter()
+                .position(|device| device.is_current(&path, product_id, serial.as_deref()))
+            {
+                detected_devices.push(known_devices.swap_remove(index));
+                continue;
+            }
+            match driver.open(&usb, device_info) {
+                Ok((wallet, info)) => {
+                    trace!("Found device: {:?}", info);
+                    opened.push(path.clone());
+                    detected_devices.push(Device {
+                        path,
+                        product_id,
+                        serial,
+                        info,
+                        wallet,
+                        failed: Arc::default(),
+                    })
+                }
+                Err(err) => {
+                    error!(
+                        "Error connecting to {} device: {}",
+                        driver.manufacturer(),
+                        err
+                    );
+                    errors.push(err)
                 }
-                Err(err) => error!("Error connecting to ledger device to read info: {}", err),
             }
         }
 
@@ -149,39 +257,193 @@ impl RemoteWalletManager {
         *self.devices.write() = detected_devices;
 
         if num_curr_devices == 0 && !errors.is_empty() {
-            return Err(errors[0].clone());
+            return Err(errors.swap_remove(0));
         }
 
-        Ok(num_curr_devices - num_prev_devices)
+        Ok(opened)
     }
 
     #[cfg(not(feature = "hidapi"))]
-    pub fn update_devices(&self) -> Result<usize, RemoteWalletError> {
+    pub(crate) fn open_devices(&self) -> Result<Vec<String>, RemoteWalletError> {
         Err(RemoteWalletError::Hid(
             "hidapi crate compilation disabled in remote-wallet.".to_string(),
         ))
     }
 
+    /// Repopulate device list and eagerly read the app configuration and base pubkey of every
+    /// device. Returns the number of devices that responded.
+    pub fn probe_devices(&self) -> Result<usize, RemoteWalletError> {
+        let scan = self.scan_devices()?;
+        let num_probed = scan.values().filter(|result| result.is_ok()).count();
+
+        if num_probed == 0 {
+            if let Some(err) = scan.into_values().find_map(Result::err) {
+                return Err(err);
+            }
+        }
+
+        Ok(num_probed)
+    }
+
+    /// Repopulate device list and probe every device. Returns the outcome of probing each device,
+    /// keyed by host device path, including devices that could not be read.
+    pub(crate) fn scan_devices(&self) -> Result<DeviceScan, RemoteWalletError> {
+        self.open_devices()?;
+        Ok(self.probe_known_devices(|_| true))
+    }
+
+    /// Probe the known devices whose host device path passes `filter`, see `scan_devices`
+    pub(crate) fn probe_known_devices<F>(&self, filter: F) -> DeviceScan
+    where
+        F: Fn(&str) -> bool,
+    {
+        let devices: Vec<Device> = self
+            .devices
+            .read()
+            .iter()
+            .filter(|device| filter(&device.path))
+            .cloned()
+            .collect();
+
+        // the device list lock isn't held while talking to the devices
+        devices
+            .into_iter()
+            .map(|device| {
+                let result = device
+                    .wallet
+                    .probe()
+                    .map(|_| device.info())
+                    .map_err(|err| device.check_error(err));
+                if let Err(err) = &result {
+                    error!(
+                        "Error connecting to {} to read info: {}",
+                        device.wallet.name(),
+                        err
+                    );
+                }
+                (device.path, result)
+            })
+            .collect()
+    }
+
+    /// Add `wallet` to the device list, as if it had been opened at `path`
+    #[cfg(test)]
+    pub(crate) fn insert_device(&self, path: &str, wallet: Arc<dyn RemoteWallet>) {
+        let info = RemoteWalletInfo {
+            manufacturer: wallet.manufacturer(),
+            host_device_path: path.to_string(),
+            ..RemoteWalletInfo::default()
+        };
+        self.devices.write().push(Device {
+            path: path.to_string(),
+            product_id: 0,
+            serial: None,
+            info,
+            wallet,
+            failed: Arc::default(),
+        });
+    }
+
+    /// Host device paths of the known devices
+    pub(crate) fn device_paths(&self) -> Vec<String> {
+        self.devices
+            .read()
+            .iter()
+            .map(|device| device.path.clone())
+            .collect()
+    }
+
+    /// Read the base pubkey of every device that hasn't reported it yet, so that devices can be
+    /// matched by pubkey. Failures are reported through `RemoteWalletInfo::error`.
+    pub(crate) fn fetch_base_pubkeys(&self) {
+        let devices: Vec<Device> = self.devices.read().clone();
+        for device in devices {
+            if let Err(err) = device.wallet.get_base_pubkey() {
+                device.check_error(err);
+            }
+        }
+    }
+
     /// List connected and acknowledged wallets
     pub fn list_devices(&self) -> Vec<RemoteWalletInfo> {
-        self.devices.read().iter().map(|d| d.info.clone()).collect()
+        self.devices.read().iter().map(Device::info).collect()
     }
 
     /// Get a particular wallet
-    #[allow(unreachable_patterns)]
-    pub fn get_ledger(
+    pub fn get_wallet(
         &self,
         host_device_path: &str,
-    ) -> Result<Arc<LedgerWallet>, RemoteWalletError> {
+    ) -> Result<Arc<dyn RemoteWallet>, RemoteWalletError> {
         self.devices
             .read()
             .iter()
             .find(|device| device.info.host_device_path == host_device_path)
+            .map(|device| device.wallet.clone())
             .ok_or(RemoteWalletError::PubkeyNotFound)
-            .and_then(|device| match &device.wallet_type {
-                RemoteWalletType::Ledger(ledger) => Ok(ledger.clone()),
-                _ => Err(RemoteWalletError::DeviceTypeMismatch),
-            })
+    }
+
+    /// Get a particular Ledger wallet
+    pub fn get_ledger(
+        &self,
+        host_device_path: &str,
+    ) -> Result<Arc<LedgerWallet>, RemoteWalletError> {
+        self.get_wallet(host_device_path)?
+            .into_any()
+            .downcast::<LedgerWallet>()
+            .map_err(|_| RemoteWalletError::DeviceTypeMismatch)
+    }
+
+    /// Choose a device based on matching info fields, using `selection` to pick between several
+    /// matching devices
+    pub fn select_device(
+        &self,
+        info: RemoteWalletInfo,
+        keypair_name: &str,
+        selection: &DeviceSelection,
+    ) -> Result<RemoteWalletInfo, RemoteWalletError> {
+        if info.pubkey != Pubkey::default() {
+            // matching by pubkey needs the base pubkey of every device
+            self.fetch_base_pubkeys();
+        }
+        let devices = self.list_devices();
+        let mut matches = devices
+            .iter()
+            .filter(|&device_info| device_info.matches(&info));
+        if matches
+            .clone()
+            .all(|device_info| device_info.error.is_some())
+        {
+            let first_device = matches.next();
+            if let Some(device) = first_device {
+                return Err(device.error.clone().unwrap());
+            }
+        }
+        let mut matches: Vec<RemoteWalletInfo> = matches
+            .filter(|&device_info| device_info.error.is_none())
+            .cloned()
+            .collect();
+        if matches.is_empty() {
+            return Err(RemoteWalletError::NoDeviceFound);
+        }
+        // a locator naming a device by identity must name exactly one
+        if info.has_device_selector() && matches.len() > 1 {
+            return Err(RemoteWalletError::AmbiguousDevice(matches.len()));
+        }
+        matches.sort_by_cached_key(RemoteWalletInfo::get_pretty_name);
+
+        let selected = selection.select(&matches, keypair_name)?;
+        Ok(matches.swap_remove(selected))
+    }
+
+    /// Choose a wallet based on matching info fields, see `select_device`
+    pub fn get_wallet_from_info(
+        &self,
+        info: RemoteWalletInfo,
+        keypair_name: &str,
+        selection: &DeviceSelection,
+    ) -> Result<Arc<dyn RemoteWallet>, RemoteWalletError> {
+        let info = self.select_device(info, keypair_name, selection)?;
+        self.get_wallet(&info.host_device_path)
     }
 
     /// Get wallet info.
@@ -189,43 +451,139 @@ impl RemoteWalletManager {
         self.devices
             .read()
             .iter()
-            .find(|d| &d.info.pubkey == pubkey)
-            .map(|d| d.info.clone())
+            .map(Device::info)
+            .find(|info| &info.pubkey == pubkey)
     }
 
-    /// Update devices in maximum `max_polling_duration` if it doesn't succeed
+    /// Update devices in maximum `max_polling_duration`, returning whether a device was found
     pub fn try_connect_polling(&self, max_polling_duration: &Duration) -> bool {
+        self.wait_for_device(max_polling_duration, &DeviceFilter::default(), |_| {})
+            .is_ok()
+    }
+
+    /// Poll for a device matching `filter` for at most `timeout`, backing off between attempts.
+    /// `on_progress` is called after every unsuccessful attempt. Returns the info of the first
+    /// matching device, or the last error encountered if none was found in time.
+    pub fn wait_for_device<F>(
+        &self,
+        timeout: &Duration,
+        filter: &DeviceFilter,
+        mut on_progress: F,
+    ) -> Result<RemoteWalletInfo, RemoteWalletError>
+    where
+        F: FnMut(&WaitProgress),
+    {
         let start_time = Instant::now();
-        while start_time.elapsed() <= *max_polling_duration {
-            if let Ok(num_devices) = self.update_devices() {
-                let plural = if num_devices == 1 { "" } else { "s" };
-                trace!("{} Remote Wallet{} found", num_devices, plural);
-                return true;
+        let mut backoff = WAIT_INITIAL_BACKOFF;
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let last_error = match self.scan_devices() {
+                Ok(scan) => {
+                    if let Some(info) = scan
+                        .values()
+                        .filter_map(|result| result.as_ref().ok())
+                        .find(|info| filter.matches(info))
+                    {
+                        trace!("Remote wallet found after {} attempt(s)", attempt);
+                        return Ok(info.clone());
+                    }
+                    scan.into_values()
+                        .find_map(Result::err)
+                        .unwrap_or(RemoteWalletError::NoDeviceFound)
+                }
+                Err(err) => err,
+            };
+
+            let elapsed = start_time.elapsed();
+            if elapsed >= *timeout {
+                return Err(last_error);
             }
+            let next_retry_in = backoff.min(*timeout - elapsed);
+            on_progress(&WaitProgress {
+                attempt,
+                elapsed,
+                next_retry_in,
+                last_error: &last_error,
+            });
+            std::thread::sleep(next_retry_in);
+            backoff = (backoff * 2).min(WAIT_MAX_BACKOFF);
         }
-        false
     }
 }
 
-/// `RemoteWallet` trait
-#[allow(unused_variables)]
-pub trait RemoteWallet<T> {
-    fn name(&self) -> &str {
-        "unimplemented"
-    }
+/// Criteria a device must meet to be returned by `RemoteWalletManager::wait_for_device`. Unset
+/// fields match any device.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DeviceFilter {
+    pub manufacturer: Option<Manufacturer>,
+    pub serial: Option<String>,
+    /// Base pubkey of the device, see `RemoteWalletInfo::pubkey`
+    pub pubkey: Option<Pubkey>,
+}
 
-    /// Parse device info and get device base pubkey
-    fn read_device(&mut self, dev_info: &T) -> Result<RemoteWalletInfo, RemoteWalletError> {
-        unimplemented!();
+impl DeviceFilter {
+    pub fn matches(&self, info: &RemoteWalletInfo) -> bool {
+        self.manufacturer
+            .is_none_or(|manufacturer| manufacturer == info.manufacturer)
+            && self
+                .serial
+                .as_ref()
+                .is_none_or(|serial| serial == &info.serial)
+            && self.pubkey.is_none_or(|pubkey| pubkey == info.pubkey)
     }
+}
+
+/// Progress of `RemoteWalletManager::wait_for_device`, reported after each unsuccessful attempt
+#[derive(Debug)]
+pub struct WaitProgress<'a> {
+    /// Number of attempts made so far
+    pub attempt: usize,
+    /// Time spent waiting so far
+    pub elapsed: Duration,
+    /// Delay before the next attempt
+    pub next_retry_in: Duration,
+    /// Why the last attempt did not find a matching device
+    pub last_error: &'a RemoteWalletError,
+}
+
+/// Hardware wallet device, as opened by a `RemoteWalletDriver`
+pub trait RemoteWallet: Send + Sync + fmt::Debug {
+    /// Human-readable device name
+    fn name(&self) -> &str;
+
+    fn manufacturer(&self) -> Manufacturer;
+
+    /// Device path based on the base pubkey, if it has been read
+    fn pretty_path(&self) -> String;
+
+    /// Whether `get_pubkey` can ask the user to confirm the key on the device
+    fn can_confirm_keys(&self) -> bool;
+
+    /// Whether the device can display an address for the user to verify
+    fn can_display_addresses(&self) -> bool;
+
+    /// Largest message `sign_message` accepts
+    fn max_message_len(&self) -> usize;
 
     /// Get pubkey from a RemoteWallet
     fn get_pubkey(
         &self,
         derivation_path: &DerivationPath,
         confirm_key: bool,
-    ) -> Result<Pubkey, RemoteWalletError> {
-        unimplemented!();
+    ) -> Result<Pubkey, RemoteWalletError>;
+
+    /// Show the address of the key at `derivation_path` to the user and wait for their
+    /// confirmation. Returns the key, along with the address the device computed for it if the
+    /// device reports it. Only supported if `can_display_addresses` is true.
+    fn display_address(
+        &self,
+        _derivation_path: &DerivationPath,
+    ) -> Result<(Pubkey, Option<String>), RemoteWalletError> {
+        Err(RemoteWalletError::InvalidInput(format!(
+            "{} cannot display addresses",
+            self.name()
+        )))
     }
 
     /// Sign transaction data with wallet managing pubkey at derivation path m/44'/540'/<account>'/<change>'.
@@ -233,31 +591,241 @@ pub trait RemoteWallet<T> {
         &self,
         derivation_path: &DerivationPath,
         data: &[u8],
-    ) -> Result<Signature, RemoteWalletError> {
-        unimplemented!();
+    ) -> Result<Signature, RemoteWalletError>;
+
+    /// Get the pubkey at `DerivationPath::default()`, which identifies the device, reading it from
+    /// the device on first use
+    fn get_base_pubkey(&self) -> Result<Pubkey, RemoteWalletError>;
+
+    /// Read identifying values from the device, refreshing those reported by `fill_info`. Fails
+    /// if the device can't be used.
+    fn probe(&self) -> Result<(), RemoteWalletError>;
+
+    /// App the device runs Spacemesh in, for devices where it is installed and configured
+    /// separately from the firmware. Devices without one have no app settings to check.
+    fn app(&self) -> Option<&dyn DeviceApp> {
+        None
+    }
+
+    /// Fill `info` with the values read from the device so far
+    fn fill_info(&self, info: RemoteWalletInfo) -> RemoteWalletInfo;
+
+    /// Convert into `Any`, to downcast to the vendor-specific wallet type
+    fn into_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync>;
+}
+
+/// Spacemesh app installed on a device, see `RemoteWallet::app`
+pub trait DeviceApp {
+    /// Whether the app signs payloads it can't interpret
+    fn is_blind_signing_enabled(&self) -> Result<bool, RemoteWalletError>;
+
+    /// Check that the app version is supported
+    fn check_app_version(&self) -> Result<(), RemoteWalletError>;
+}
+
+/// Vendor driver, detecting and opening the devices of one manufacturer
+pub trait RemoteWalletDriver: Send + Sync {
+    fn manufacturer(&self) -> Manufacturer;
+
+    /// Whether an enumerated HID device is one of this vendor's wallets
+    #[cfg(feature = "hidapi")]
+    fn is_supported(&self, device_info: &hidapi::DeviceInfo) -> bool;
+
+    /// Open a supported device. Only HID metadata is read, see `RemoteWalletManager::update_devices`.
+    #[cfg(feature = "hidapi")]
+    fn open(
+        &self,
+        usb: &hidapi::HidApi,
+        device_info: &hidapi::DeviceInfo,
+    ) -> Result<(Arc<dyn RemoteWallet>, RemoteWalletInfo), RemoteWalletError>;
+}
+
+/// Vendor drivers a `RemoteWalletManager` dispatches to, at most one per manufacturer
+#[derive(Clone)]
+pub struct DriverRegistry {
+    drivers: Vec<Arc<dyn RemoteWalletDriver>>,
+}
+
+impl DriverRegistry {
+    /// Registry without any driver
+    pub fn empty() -> Self {
+        Self { drivers: vec![] }
+    }
+
+    /// Add `driver`, replacing the driver registered for the same manufacturer, if any
+    pub fn register(&mut self, driver: Arc<dyn RemoteWalletDriver>) {
+        self.drivers
+            .retain(|registered| registered.manufacturer() != driver.manufacturer());
+        self.drivers.push(driver);
+    }
+
+    /// Get the driver for `manufacturer`
+    pub fn get(&self, manufacturer: Manufacturer) -> Option<&Arc<dyn RemoteWalletDriver>> {
+        self.drivers
+            .iter()
+            .find(|driver| driver.manufacturer() == manufacturer)
+    }
+
+    pub fn drivers(&self) -> impl Iterator<Item = &Arc<dyn RemoteWalletDriver>> {
+        self.drivers.iter()
+    }
+
+    pub fn manufacturers(&self) -> Vec<Manufacturer> {
+        self.drivers
+            .iter()
+            .map(|driver| driver.manufacturer())
+            .collect()
+    }
+}
+
+impl Default for DriverRegistry {
+    /// Registry with the drivers of every supported vendor
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register(Arc::new(LedgerDriver));
+        registry
+    }
+}
+
+impl fmt::Debug for DriverRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DriverRegistry")
+            .field("manufacturers", &self.manufacturers())
+            .finish()
     }
 }
 
 /// `RemoteWallet` device
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Device {
-    #[allow(dead_code)]
     pub(crate) path: String,
+    /// HID product ID and serial number the device was opened with
+    pub(crate) product_id: u16,
+    pub(crate) serial: Option<String>,
     pub(crate) info: RemoteWalletInfo,
-    pub wallet_type: RemoteWalletType,
+    pub wallet: Arc<dyn RemoteWallet>,
+    /// Set once talking to the device failed at the transport level, so that it gets reopened
+    failed: Arc<AtomicBool>,
 }
 
-/// Remote wallet convenience enum to hold various wallet types
-#[derive(Debug)]
-pub enum RemoteWalletType {
-    Ledger(Arc<LedgerWallet>),
+impl Device {
+    /// Device info, including the values read from the device so far
+    fn info(&self) -> RemoteWalletInfo {
+        self.wallet.fill_info(self.info.clone())
+    }
+
+    /// Whether this device is still the one enumerated at `path` with `product_id` and `serial`,
+    /// and can keep being used as is
+    fn is_current(&self, path: &str, product_id: u16, serial: Option<&str>) -> bool {
+        self.path == path
+            && self.product_id == product_id
+            && self.serial.as_deref() == serial
+            && !self.failed.load(Ordering::Relaxed)
+    }
+
+    /// Flag the device for reopening if `err` is a transport error, passing `err` through
+    fn check_error(&self, err: RemoteWalletError) -> RemoteWalletError {
+        if let RemoteWalletError::Hid(_) = err {
+            self.failed.store(true, Ordering::Relaxed);
+        }
+        err
+    }
+}
+
+/// Host-supplied device chooser, see `DeviceSelection::Callback`
+pub type DeviceSelectionCallback = Box<dyn Fn(&[RemoteWalletInfo]) -> Option<usize> + Send + Sync>;
+
+/// Strategy for choosing a device when several connected devices match a `Locator`
+pub enum DeviceSelection {
+    /// Use the first matching device, ordered by pretty path and model
+    FirstMatch,
+    /// Use the matching device with this serial number
+    Serial(String),
+    /// Use the matching device at this host device path
+    HostDevicePath(String),
+    /// Fail with `AmbiguousDevice` unless exactly one device matches
+    Unique,
+    /// Let the host choose: given the matching devices, return the index of the selected one,
+    /// or `None` to cancel
+    Callback(DeviceSelectionCallback),
+    /// Prompt the user on the terminal. Requires an interactive TTY.
+    Interactive,
+}
+
+impl DeviceSelection {
+    /// Select one of `devices`, returning its index. `keypair_name` is only used to prompt the
+    /// user in `Interactive` mode.
+    pub fn select(
+        &self,
+        devices: &[RemoteWalletInfo],
+        keypair_name: &str,
+    ) -> Result<usize, RemoteWalletError> {
+        if devices.is_empty() {
+            return Err(RemoteWalletError::NoDeviceFound);
+        }
+        match self {
+            Self::FirstMatch => Ok(0),
+            Self::Serial(serial) => devices
+                .iter()
+                .position(|device| &device.serial == serial)
+                .ok_or(RemoteWalletError::NoDeviceFound),
+            Self::HostDevicePath(host_device_path) => devices
+                .iter()
+                .position(|device| &device.host_device_path == host_device_path)
+                .ok_or(RemoteWalletError::NoDeviceFound),
+            Self::Unique => match devices.len() {
+                1 => Ok(0),
+                n => Err(RemoteWalletError::AmbiguousDevice(n)),
+            },
+            Self::Callback(callback) => match callback(devices) {
+                Some(index) if index < devices.len() => Ok(index),
+                Some(index) => Err(RemoteWalletError::InvalidInput(format!(
+                    "selected device index {index} out of range"
+                ))),
+                None => Err(RemoteWalletError::UserCancel),
+            },
+            Self::Interactive => {
+                if devices.len() == 1 {
+                    return Ok(0);
+                }
+                let items: Vec<String> = devices
+                    .iter()
+                    .map(RemoteWalletInfo::get_pretty_name)
+                    .collect();
+                Select::with_theme(&ColorfulTheme::default())
+                    .with_prompt(format!(
+                        "Multiple hardware wallets found. Please select a device for {keypair_name:?}"
+                    ))
+                    .default(0)
+                    .items(&items[..])
+                    .interact_opt()
+                    .map_err(|err| {
+                        RemoteWalletError::InvalidInput(format!("device selection prompt: {err}"))
+                    })?
+                    .ok_or(RemoteWalletError::UserCancel)
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for DeviceSelection {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::FirstMatch => write!(f, "FirstMatch"),
+            Self::Serial(serial) => write!(f, "Serial({serial:?})"),
+            Self::HostDevicePath(path) => write!(f, "HostDevicePath({path:?})"),
+            Self::Unique => write!(f, "Unique"),
+            Self::Callback(_) => write!(f, "Callback"),
+            Self::Interactive => write!(f, "Interactive"),
+        }
+    }
 }
 
 /// Remote wallet information.
 #[derive(Debug, Default, Clone)]
 pub struct RemoteWalletInfo {
-    /// RemoteWallet device model
-    pub model: String,
+    /// RemoteWallet device model, if known
+    pub model: Option<DeviceModel>,
     /// RemoteWallet device manufacturer
     pub manufacturer: Manufacturer,
     /// RemoteWallet device serial number
@@ -268,6 +836,10 @@ pub struct RemoteWalletInfo {
     pub pubkey: Pubkey,
     /// Initial read error
     pub error: Option<RemoteWalletError>,
+    /// Device app settings, if they could be read
+    pub settings: Option<LedgerSettings>,
+    /// App running on the device, if it could be read
+    pub app: Option<RunningApp>,
 }
 
 impl RemoteWalletInfo {
@@ -275,19 +847,45 @@ impl RemoteWalletInfo {
         RemoteWalletInfo {
             manufacturer: locator.manufacturer,
             pubkey: locator.pubkey.unwrap_or_default(),
+            serial: locator.serial.unwrap_or_default(),
+            model: locator.model,
+            host_device_path: locator.host_device_path.unwrap_or_default(),
             ..RemoteWalletInfo::default()
         }
     }
 
+    /// Whether the running app is an outdated Spacemesh app that must be updated before use
+    pub fn needs_app_update(&self) -> bool {
+        self.app.as_ref().is_some_and(RunningApp::needs_update)
+    }
+
+    /// Whether this info narrows the device by serial, model or host path
+    pub(crate) fn has_device_selector(&self) -> bool {
+        !self.serial.is_empty() || self.model.is_some() || !self.host_device_path.is_empty()
+    }
+
     pub fn get_pretty_path(&self) -> String {
         format!("usb://{}/{:?}", self.manufacturer, self.pubkey,)
     }
 
+    /// Pretty path followed by the model, used to order and list devices
+    fn get_pretty_name(&self) -> String {
+        match self.model {
+            Some(model) => format!("{} ({})", self.get_pretty_path(), model),
+            None => self.get_pretty_path(),
+        }
+    }
+
     pub(crate) fn matches(&self, other: &Self) -> bool {
+        // empty fields act as wildcards
+        let selector_matches = |a: &str, b: &str| a == b || a.is_empty() || b.is_empty();
         self.manufacturer == other.manufacturer
             && (self.pubkey == other.pubkey
                 || self.pubkey == Pubkey::default()
                 || other.pubkey == Pubkey::default())
+            && selector_matches(&self.serial, &other.serial)
+            && (self.model == other.model || self.model.is_none() || other.model.is_none())
+            && selector_matches(&self.host_device_path, &other.host_device_path)
     }
 }
 
@@ -322,51 +920,86 @@ pub fn maybe_wallet_manager() -> Result<Option<Arc<RemoteWalletManager>>, Remote
 
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use {super::*, crate::ledger_model::LedgerModel};
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_wallet_manager_is_send_sync() {
+        assert_send_sync::<RemoteWalletManager>();
+        assert_send_sync::<Arc<RemoteWalletManager>>();
+        assert_send_sync::<LedgerWallet>();
+    }
 
     #[test]
     fn test_parse_locator() {
-        let pubkey = solana_sdk::pubkey::new_rand();
+        let pubkey = Pubkey::new_unique();
         let locator = Locator {
             manufacturer: Manufacturer::Ledger,
             pubkey: Some(pubkey),
+            ..Locator::default()
         };
         let wallet_info = RemoteWalletInfo::parse_locator(locator);
         assert!(wallet_info.matches(&RemoteWalletInfo {
-            model: "nano-s".to_string(),
+            model: Some(DeviceModel::Ledger(LedgerModel::NanoS)),
             manufacturer: Manufacturer::Ledger,
             serial: "".to_string(),
             host_device_path: "/host/device/path".to_string(),
             pubkey,
             error: None,
+            settings: None,
+            app: None,
         }));
 
         // Test that pubkey need not be populated
         let locator = Locator {
             manufacturer: Manufacturer::Ledger,
             pubkey: None,
+            ..Locator::default()
         };
         let wallet_info = RemoteWalletInfo::parse_locator(locator);
         assert!(wallet_info.matches(&RemoteWalletInfo {
-            model: "nano-s".to_string(),
+            model: Some(DeviceModel::Ledger(LedgerModel::NanoS)),
             manufacturer: Manufacturer::Ledger,
             serial: "".to_string(),
             host_device_path: "/host/device/path".to_string(),
             pubkey: Pubkey::default(),
             error: None,
+            settings: None,
+            app: None,
         }));
+
+        // Test that device selectors are honoured
+        let locator = Locator::new_from_path("usb://ledger?serial=0001&model=Nano%20S").unwrap();
+        let wallet_info = RemoteWalletInfo::parse_locator(locator);
+        assert!(wallet_info.has_device_selector());
+        let mut device_info = RemoteWalletInfo {
+            model: Some(DeviceModel::Ledger(LedgerModel::NanoS)),
+            manufacturer: Manufacturer::Ledger,
+            serial: "0001".to_string(),
+            host_device_path: "/host/device/path".to_string(),
+            pubkey: Pubkey::new_unique(),
+            error: None,
+            settings: None,
+            app: None,
+        };
+        assert!(wallet_info.matches(&device_info));
+        device_info.serial = "0002".to_string();
+        assert!(!wallet_info.matches(&device_info));
     }
 
     #[test]
     fn test_remote_wallet_info_matches() {
-        let pubkey = solana_sdk::pubkey::new_rand();
+        let pubkey = Pubkey::new_unique();
         let info = RemoteWalletInfo {
             manufacturer: Manufacturer::Ledger,
-            model: "Nano S".to_string(),
+            model: Some(DeviceModel::Ledger(LedgerModel::NanoS)),
             serial: "0001".to_string(),
             host_device_path: "/host/device/path".to_string(),
             pubkey,
             error: None,
+            settings: None,
+            app: None,
         };
         let mut test_info = RemoteWalletInfo {
             manufacturer: Manufacturer::Unknown,
@@ -375,34 +1008,181 @@ mod tests {
         assert!(!info.matches(&test_info));
         test_info.manufacturer = Manufacturer::Ledger;
         assert!(info.matches(&test_info));
-        test_info.model = "Other".to_string();
+        test_info.model = Some(DeviceModel::Ledger(LedgerModel::NanoX));
+        assert!(!info.matches(&test_info));
+        test_info.model = Some(DeviceModel::Ledger(LedgerModel::NanoS));
         assert!(info.matches(&test_info));
-        test_info.model = "Nano S".to_string();
+        test_info.serial = "0002".to_string();
+        assert!(!info.matches(&test_info));
+        test_info.serial = "0001".to_string();
         assert!(info.matches(&test_info));
+        test_info.host_device_path = "/other/device/path".to_string();
+        assert!(!info.matches(&test_info));
         test_info.host_device_path = "/host/device/path".to_string();
         assert!(info.matches(&test_info));
-        let another_pubkey = solana_sdk::pubkey::new_rand();
+        let another_pubkey = Pubkey::new_unique();
         test_info.pubkey = another_pubkey;
         assert!(!info.matches(&test_info));
         test_info.pubkey = pubkey;
         assert!(info.matches(&test_info));
     }
 
+    #[test]
+    fn test_device_selection() {
+        let devices: Vec<RemoteWalletInfo> = ["0001", "0002"]
+            .iter()
+            .map(|serial| RemoteWalletInfo {
+                model: Some(DeviceModel::Ledger(LedgerModel::NanoS)),
+                manufacturer: Manufacturer::Ledger,
+                serial: serial.to_string(),
+                host_device_path: format!("/host/device/{serial}"),
+                pubkey: Pubkey::default(),
+                error: None,
+                settings: None,
+                app: None,
+            })
+            .collect();
+
+        assert_eq!(
+            DeviceSelection::FirstMatch
+                .select(&devices, "main")
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            DeviceSelection::Serial("0002".to_string())
+                .select(&devices, "main")
+                .unwrap(),
+            1
+        );
+        assert!(matches!(
+            DeviceSelection::Serial("0003".to_string()).select(&devices, "main"),
+            Err(RemoteWalletError::NoDeviceFound)
+        ));
+        assert_eq!(
+            DeviceSelection::HostDevicePath("/host/device/0002".to_string())
+                .select(&devices, "main")
+                .unwrap(),
+            1
+        );
+        assert!(matches!(
+            DeviceSelection::Unique.select(&devices, "main"),
+            Err(RemoteWalletError::AmbiguousDevice(2))
+        ));
+        assert_eq!(
+            DeviceSelection::Unique
+                .select(&devices[..1], "main")
+                .unwrap(),
+            0
+        );
+        assert!(matches!(
+            DeviceSelection::Unique.select(&[], "main"),
+            Err(RemoteWalletError::NoDeviceFound)
+        ));
+
+        let callback = DeviceSelection::Callback(Box::new(|devices| {
+            devices.iter().position(|device| device.serial == "0002")
+        }));
+        assert_eq!(callback.select(&devices, "main").unwrap(), 1);
+        let callback = DeviceSelection::Callback(Box::new(|_| None));
+        assert!(matches!(
+            callback.select(&devices, "main"),
+            Err(RemoteWalletError::UserCancel)
+        ));
+        let callback = DeviceSelection::Callback(Box::new(|_| Some(2)));
+        assert!(matches!(
+            callback.select(&devices, "main"),
+            Err(RemoteWalletError::InvalidInput(_))
+        ));
+    }
+
     #[test]
     fn test_get_pretty_path() {
-        let pubkey = solana_sdk::pubkey::new_rand();
+        let pubkey = Pubkey::new_unique();
         let pubkey_str = pubkey.to_string();
         let remote_wallet_info = RemoteWalletInfo {
-            model: "nano-s".to_string(),
+            model: Some(DeviceModel::Ledger(LedgerModel::NanoS)),
             manufacturer: Manufacturer::Ledger,
             serial: "".to_string(),
             host_device_path: "/host/device/path".to_string(),
             pubkey,
             error: None,
+            settings: None,
+            app: None,
         };
         assert_eq!(
             remote_wallet_info.get_pretty_path(),
             format!("usb://ledger/{}", pubkey_str)
         );
     }
+
+    #[test]
+    fn test_signer_error_from_ledger_error() {
+        let err = RemoteWalletError::from(LedgerError::UserRefused);
+        assert_eq!(err.status_word(), Some(0x5501));
+        assert!(matches!(SignerError::from(err), SignerError::UserCancel(_)));
+
+        let err = RemoteWalletError::from(LedgerError::DeviceLocked);
+        assert!(matches!(SignerError::from(err), SignerError::Connection(_)));
+
+        let err = RemoteWalletError::from(LedgerError::Unknown(0x6fff));
+        assert_eq!(err.status_word(), Some(0x6fff));
+        match SignerError::from(err) {
+            SignerError::Protocol(msg) => assert!(msg.contains("0x6fff")),
+            err => panic!("unexpected error: {err:?}"),
+        }
+
+        assert_eq!(RemoteWalletError::NoDeviceFound.status_word(), None);
+    }
+
+    #[test]
+    fn test_device_filter() {
+        let pubkey = Pubkey::new_unique();
+        let info = RemoteWalletInfo {
+            model: Some(DeviceModel::Ledger(LedgerModel::NanoS)),
+            manufacturer: Manufacturer::Ledger,
+            serial: "0001".to_string(),
+            host_device_path: "/host/device/path".to_string(),
+            pubkey,
+            error: None,
+            settings: None,
+            app: None,
+        };
+        assert!(DeviceFilter::default().matches(&info));
+        assert!(DeviceFilter {
+            manufacturer: Some(Manufacturer::Ledger),
+            serial: Some("0001".to_string()),
+            pubkey: Some(pubkey),
+        }
+        .matches(&info));
+        assert!(!DeviceFilter {
+            manufacturer: Some(Manufacturer::Unknown),
+            ..DeviceFilter::default()
+        }
+        .matches(&info));
+        assert!(!DeviceFilter {
+            serial: Some("0002".to_string()),
+            ..DeviceFilter::default()
+        }
+        .matches(&info));
+        assert!(!DeviceFilter {
+            pubkey: Some(Pubkey::default()),
+            ..DeviceFilter::default()
+        }
+        .matches(&info));
+    }
+
+    #[test]
+    fn test_driver_registry() {
+        let registry = DriverRegistry::default();
+        assert_eq!(registry.manufacturers(), vec![Manufacturer::Ledger]);
+        assert!(registry.get(Manufacturer::Ledger).is_some());
+        assert!(registry.get(Manufacturer::Unknown).is_none());
+
+        let mut registry = DriverRegistry::empty();
+        assert!(registry.get(Manufacturer::Ledger).is_none());
+        registry.register(Arc::new(LedgerDriver));
+        registry.register(Arc::new(LedgerDriver));
+        assert_eq!(registry.manufacturers(), vec![Manufacturer::Ledger]);
+    }
 }
@@ -0,0 +1,510 @@
+use {
+    crate::{
+        keystore::{self, Account, KeystoreError, WalletFile},
+        locator::{Locator, LocatorError},
+        remote_keypair::{generate_remote_keypair, RemoteKeypair},
+        remote_wallet::{
+            maybe_wallet_manager, DeviceSelection, RemoteWalletError, RemoteWalletManager,
+        },
+    },
+    dialoguer::{theme::ColorfulTheme, Password},
+    percent_encoding::percent_decode_str,
+    spacemesh_derivation_path::{DerivationPath, DerivationPathError},
+    spacemesh_signer::{Signer, SignerError, SoftwareKeypair},
+    std::{path::PathBuf, sync::Arc},
+    thiserror::Error,
+    uriparse::URIReference,
+};
+
+const SCHEME_USB: &str = "usb";
+const SCHEME_FILE: &str = "file";
+const SCHEME_PROMPT: &str = "prompt";
+
+/// Error resolving a signer source
+#[derive(Error, Debug, Clone)]
+pub enum SignerSourceError {
+    #[error("unsupported signer source scheme `{0}`")]
+    UnsupportedScheme(String),
+
+    #[error("invalid signer source: {0}")]
+    InvalidSource(String),
+
+    #[error("signer source prompt failed: {0}")]
+    Prompt(String),
+
+    #[error("signer source prompt cancelled by the user")]
+    PromptCancelled,
+
+    #[error("hardware wallet key doesn't match the one of keystore account `{0}`")]
+    AccountKeyMismatch(String),
+
+    #[error(transparent)]
+    DerivationPathError(#[from] DerivationPathError),
+
+    #[error(transparent)]
+    LocatorError(#[from] LocatorError),
+
+    #[error(transparent)]
+    RemoteWalletError(#[from] RemoteWalletError),
+
+    #[error(transparent)]
+    KeystoreError(#[from] KeystoreError),
+
+    #[error(transparent)]
+    SignerError(#[from] SignerError),
+}
+
+/// Where the key of a signer lives
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignerSourceKind {
+    /// Hardware wallet, e.g. `usb://ledger?key=0/0`
+    Usb(Locator),
+    /// Encrypted smapp or smcli wallet file, given as a plain path or a `file://` URI
+    Keystore(PathBuf),
+    /// Mnemonic typed in by the user, e.g. `prompt://?full-path=m/44'/540'/0'/0'/0'`
+    Prompt,
+}
+
+/// Parsed signer source: the key location, plus the derivation path from its query string, if any
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignerSource {
+    pub kind: SignerSourceKind,
+    pub derivation_path: Option<DerivationPath>,
+}
+
+impl SignerSource {
+    pub fn parse<S: AsRef<str>>(source: S) -> Result<Self, SignerSourceError> {
+        let source = source.as_ref();
+        let uri = match URIReference::try_from(source) {
+            Ok(uri) => uri,
+            Err(_) => return Ok(Self::keystore(source)),
+        };
+        let scheme = match uri.scheme() {
+            // Single-letter schemes are Windows drive letters
+            Some(scheme) if scheme.as_str().len() > 1 => scheme.as_str().to_ascii_lowercase(),
+            _ => return Ok(Self::keystore(source)),
+        };
+        match scheme.as_str() {
+            SCHEME_USB => Ok(Self {
+                kind: SignerSourceKind::Usb(Locator::new_from_uri(&uri)?),
+                derivation_path: DerivationPath::from_uri_key_query(&uri)?,
+            }),
+            SCHEME_FILE => {
+                let path = percent_decode_str(&uri.path().to_string())
+                    .decode_utf8()
+                    .map_err(|err| {
+                        SignerSourceError::InvalidSource(format!(
+                            "invalid file path in `{source}`: {err}"
+                        ))
+                    })?
+                    .into_owned();
+                if path.is_empty() {
+                    return Err(SignerSourceError::InvalidSource(format!(
+                        "missing file path in `{source}`"
+                    )));
+                }
+                Ok(Self {
+                    kind: SignerSourceKind::Keystore(PathBuf::from(path)),
+                    derivation_path: DerivationPath::from_uri_any_query(&uri)?,
+                })
+            }
+            SCHEME_PROMPT => Ok(Self {
+                kind: SignerSourceKind::Prompt,
+                derivation_path: DerivationPath::from_uri_any_query(&uri)?,
+            }),
+            _ => Err(SignerSourceError::UnsupportedScheme(scheme)),
+        }
+    }
+
+    fn keystore(path: &str) -> Self {
+        Self {
+            kind: SignerSourceKind::Keystore(PathBuf::from(path)),
+            derivation_path: None,
+        }
+    }
+}
+
+/// Host-supplied secret prompt, see `SecretPrompt::Callback`
+pub type SecretPromptCallback = Box<dyn Fn(&str) -> Option<String> + Send + Sync>;
+
+/// How mnemonics, passphrases and keystore passwords are read
+pub enum SecretPrompt {
+    /// Read from the terminal without echo. Requires an interactive TTY.
+    Terminal,
+    /// Let the host ask: given the prompt, return the secret, or `None` to cancel
+    Callback(SecretPromptCallback),
+}
+
+impl SecretPrompt {
+    pub fn prompt(&self, prompt: &str) -> Result<String, SignerSourceError> {
+        match self {
+            Self::Terminal => Password::with_theme(&ColorfulTheme::default())
+                .with_prompt(prompt)
+                .allow_empty_password(true)
+                .interact()
+                .map_err(|err| SignerSourceError::Prompt(err.to_string())),
+            Self::Callback(callback) => callback(prompt).ok_or(SignerSourceError::PromptCancelled),
+        }
+    }
+}
+
+/// Options for `signer_from_path`
+pub struct SignerFromPathConfig {
+    /// Ask the user to confirm the pubkey on hardware wallets
+    pub confirm_key: bool,
+    /// Device to use when several hardware wallets match
+    pub selection: DeviceSelection,
+    pub prompt: SecretPrompt,
+    /// Ask for a BIP39 passphrase after the mnemonic of `prompt://` sources
+    pub bip39_passphrase: bool,
+}
+
+impl Default for SignerFromPathConfig {
+    fn default() -> Self {
+        Self {
+            confirm_key: false,
+            selection: DeviceSelection::Interactive,
+            prompt: SecretPrompt::Terminal,
+            bip39_passphrase: false,
+        }
+    }
+}
+
+/// Resolve `path` to a signer. `path` is either a hardware wallet URI (`usb://`), a `prompt://`
+/// URI for a mnemonic typed in by the user, or the path of an encrypted wallet file. Without a
+/// derivation path in the query string, hardware wallets and mnemonics use `m/44'/540'` and
+/// wallet files their first mnemonic account. A wallet file account held by a hardware wallet is
+/// resolved on the device of its locator. `keypair_name` names the key in prompts.
+/// `wallet_manager` is initialized on first use of a hardware wallet.
+pub fn signer_from_path(
+    path: &str,
+    keypair_name: &str,
+    wallet_manager: &mut Option<Arc<RemoteWalletManager>>,
+    config: &SignerFromPathConfig,
+) -> Result<Box<dyn Signer>, SignerSourceError> {
+    let SignerSource {
+        kind,
+        derivation_path,
+    } = SignerSource::parse(path)?;
+    match kind {
+        SignerSourceKind::Usb(locator) => Ok(Box::new(remote_keypair(
+            locator,
+            derivation_path.unwrap_or_default(),
+            keypair_name,
+            wallet_manager,
+            config,
+        )?)),
+        SignerSourceKind::Keystore(file) => {
+            let wallet = WalletFile::read(file)?;
+            let password = config
+                .prompt
+                .prompt(&format!("Password for {keypair_name}"))?;
+            let secrets = wallet.decrypt(&password)?;
+            let derivation_path = match derivation_path {
+                Some(derivation_path) => derivation_path,
+                None => secrets.default_derivation_path()?,
+            };
+            let account = secrets
+                .accounts
+                .iter()
+                .find(|account| account.derivation_path().ok().as_ref() == Some(&derivation_path));
+            if let Some(account) = account.filter(|account| account.is_remote()) {
+                return Ok(Box::new(remote_account_keypair(
+                    account,
+                    derivation_path,
+                    keypair_name,
+                    wallet_manager,
+                    config,
+                )?));
+            }
+            Ok(Box::new(SoftwareKeypair::from_seed(
+                &secrets.seed()?,
+                derivation_path,
+            )?))
+        }
+        SignerSourceKind::Prompt => {
+            let mnemonic = config
+                .prompt
+                .prompt(&format!("Seed phrase for {keypair_name}"))?;
+            let passphrase = if config.bip39_passphrase {
+                config.prompt.prompt(&format!(
+                    "BIP39 passphrase for {keypair_name} (empty for none)"
+                ))?
+            } else {
+                String::new()
+            };
+            let seed = keystore::mnemonic_to_seed(&mnemonic, &passphrase)?;
+            Ok(Box::new(SoftwareKeypair::from_seed(
+                &seed,
+                derivation_path.unwrap_or_default(),
+            )?))
+        }
+    }
+}
+
+/// Key at `derivation_path` on the hardware wallet matching `locator`, initializing
+/// `wallet_manager` on first use
+fn remote_keypair(
+    locator: Locator,
+    derivation_path: DerivationPath,
+    keypair_name: &str,
+    wallet_manager: &mut Option<Arc<RemoteWalletManager>>,
+    config: &SignerFromPathConfig,
+) -> Result<RemoteKeypair, SignerSourceError> {
+    if wallet_manager.is_none() {
+        *wallet_manager = maybe_wallet_manager()?;
+    }
+    let wallet_manager = wallet_manager
+        .as_ref()
+        .ok_or(RemoteWalletError::NoDeviceFound)?;
+    Ok(generate_remote_keypair(
+        locator,
+        derivation_path,
+        wallet_manager,
+        config.confirm_key,
+        keypair_name,
+        &config.selection,
+    )?)
+}
+
+/// Key of a wallet file account held by a hardware wallet, which must be the key the account
+/// was created with
+fn remote_account_keypair(
+    account: &Account,
+    derivation_path: DerivationPath,
+    keypair_name: &str,
+    wallet_manager: &mut Option<Arc<RemoteWalletManager>>,
+    config: &SignerFromPathConfig,
+) -> Result<RemoteKeypair, SignerSourceError> {
+    let locator = account.locator()?.unwrap_or_default();
+    let keypair = remote_keypair(
+        locator,
+        derivation_path,
+        keypair_name,
+        wallet_manager,
+        config,
+    )?;
+    if keypair.pubkey != account.pubkey()? {
+        return Err(SignerSourceError::AccountKeyMismatch(
+            account.display_name.clone(),
+        ));
+    }
+    Ok(keypair)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            keystore::tests::{encrypt_wallet, test_secrets, MNEMONIC},
+            locator::Manufacturer,
+        },
+    };
+    #[cfg(feature = "hidapi")]
+    use {
+        crate::{
+            keystore::WalletSecrets,
+            ledger::{tests::MockDevice, LedgerWallet},
+        },
+        parking_lot::Mutex,
+        spacemesh_signer::Pubkey,
+    };
+
+    fn scripted_config(answers: &'static [&'static str]) -> SignerFromPathConfig {
+        let next = std::sync::atomic::AtomicUsize::new(0);
+        SignerFromPathConfig {
+            prompt: SecretPrompt::Callback(Box::new(move |_prompt| {
+                let index = next.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                answers.get(index).map(|answer| answer.to_string())
+            })),
+            ..SignerFromPathConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_signer_source() {
+        let source = SignerSource::parse("usb://ledger?key=0/0").unwrap();
+        assert_eq!(
+            source.kind,
+            SignerSourceKind::Usb(Locator {
+                manufacturer: Manufacturer::Ledger,
+                ..Locator::default()
+            })
+        );
+        assert_eq!(
+            source.derivation_path,
+            Some(DerivationPath::new_bip44(Some(0), Some(0)))
+        );
+
+        let source = SignerSource::parse("prompt://?full-path=m/44'/540'/1'").unwrap();
+        assert_eq!(source.kind, SignerSourceKind::Prompt);
+        assert_eq!(
+            source.derivation_path,
+            Some(DerivationPath::new_bip44(Some(1), None))
+        );
+
+        let source = SignerSource::parse("file:///tmp/wallet.json?key=2").unwrap();
+        assert_eq!(
+            source.kind,
+            SignerSourceKind::Keystore(PathBuf::from("/tmp/wallet.json"))
+        );
+        assert_eq!(
+            source.derivation_path,
+            Some(DerivationPath::new_bip44(Some(2), None))
+        );
+
+        let source = SignerSource::parse("file:///tmp/my%20wallets/%C3%A9t%C3%A9.json").unwrap();
+        assert_eq!(
+            source.kind,
+            SignerSourceKind::Keystore(PathBuf::from("/tmp/my wallets/été.json"))
+        );
+        assert!(matches!(
+            SignerSource::parse("file:///tmp/%FF.json"),
+            Err(SignerSourceError::InvalidSource(_))
+        ));
+
+        for path in ["wallet.json", "/tmp/wallet.json", "C:\\wallet.json"] {
+            assert_eq!(
+                SignerSource::parse(path).unwrap(),
+                SignerSource {
+                    kind: SignerSourceKind::Keystore(PathBuf::from(path)),
+                    derivation_path: None,
+                }
+            );
+        }
+
+        assert!(matches!(
+            SignerSource::parse("ftp://host/wallet.json"),
+            Err(SignerSourceError::UnsupportedScheme(scheme)) if scheme == "ftp"
+        ));
+        assert!(matches!(
+            SignerSource::parse("usb://ledger?key=0/0/0/0"),
+            Err(SignerSourceError::DerivationPathError(_))
+        ));
+    }
+
+    #[test]
+    fn test_signer_from_prompt() {
+        let seed = keystore::mnemonic_to_seed(MNEMONIC, "").unwrap();
+        let expected =
+            SoftwareKeypair::from_seed(&seed, DerivationPath::new_bip44(Some(0), None)).unwrap();
+
+        let signer = signer_from_path(
+            "prompt://?key=0",
+            "test",
+            &mut None,
+            &scripted_config(&[MNEMONIC]),
+        )
+        .unwrap();
+        assert_eq!(signer.pubkey(), expected.pubkey());
+        assert!(!signer.is_interactive());
+
+        assert!(matches!(
+            signer_from_path("prompt://", "test", &mut None, &scripted_config(&[])),
+            Err(SignerSourceError::PromptCancelled)
+        ));
+        assert!(matches!(
+            signer_from_path(
+                "prompt://",
+                "test",
+                &mut None,
+                &scripted_config(&["not a mnemonic"])
+            ),
+            Err(SignerSourceError::KeystoreError(
+                KeystoreError::InvalidMnemonic(_)
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_signer_from_keystore() {
+        let wallet = encrypt_wallet(&test_secrets(), "password");
+        let file = std::env::temp_dir().join(format!(
+            "spacemesh-signer-source-{}.json",
+            std::process::id()
+        ));
+        std::fs::write(&file, serde_json::to_string(&wallet).unwrap()).unwrap();
+        let path = file.to_str().unwrap();
+
+        let seed = keystore::mnemonic_to_seed(MNEMONIC, "").unwrap();
+        let first_account = SoftwareKeypair::from_seed(
+            &seed,
+            DerivationPath::from_absolute_path_str("m/44'/540'/0'/0'/0'").unwrap(),
+        )
+        .unwrap();
+        let signer =
+            signer_from_path(path, "test", &mut None, &scripted_config(&["password"])).unwrap();
+        assert_eq!(signer.pubkey(), first_account.pubkey());
+
+        let wrong_password =
+            signer_from_path(path, "test", &mut None, &scripted_config(&["wrong"]));
+        std::fs::remove_file(&file).unwrap();
+        assert!(matches!(
+            wrong_password,
+            Err(SignerSourceError::KeystoreError(
+                KeystoreError::WrongPassword
+            ))
+        ));
+    }
+
+    #[cfg(feature = "hidapi")]
+    #[test]
+    fn test_signer_from_keystore_remote_account() {
+        let device = MockDevice::new();
+        let device_pubkey = device.keypair.pubkey();
+        let usb = hidapi::HidApi::new_without_enumerate().unwrap();
+        let wallet_manager = RemoteWalletManager::new(Arc::new(Mutex::new(usb)));
+        wallet_manager.insert_device("/dev/ledger", Arc::new(LedgerWallet::new(device)));
+
+        let locator = Locator::new_from_path("usb://ledger").unwrap();
+        let remote_path = DerivationPath::from_absolute_path_str("m/44'/540'/1'/0'/0'").unwrap();
+        let write_wallet = |pubkey: &Pubkey| {
+            let mut secrets: WalletSecrets = serde_json::from_value(test_secrets()).unwrap();
+            secrets.accounts.push(Account::remote(
+                "Ledger account",
+                "2024-01-01T00:00:00.000Z",
+                &locator,
+                &remote_path,
+                pubkey,
+            ));
+            let wallet = encrypt_wallet(&serde_json::to_value(&secrets).unwrap(), "password");
+            let file = std::env::temp_dir().join(format!(
+                "spacemesh-signer-source-remote-{}.json",
+                std::process::id()
+            ));
+            std::fs::write(&file, serde_json::to_string(&wallet).unwrap()).unwrap();
+            file
+        };
+        let config = || SignerFromPathConfig {
+            selection: DeviceSelection::FirstMatch,
+            ..scripted_config(&["password"])
+        };
+
+        let file = write_wallet(&device_pubkey);
+        let path = format!("{}?full-path=m/44'/540'/1'/0'/0'", file.to_str().unwrap());
+        let signer = signer_from_path(
+            &format!("file://{path}"),
+            "test",
+            &mut Some(wallet_manager.clone()),
+            &config(),
+        )
+        .unwrap();
+        assert_eq!(signer.pubkey(), device_pubkey);
+        assert!(signer.is_interactive());
+
+        // the device holds another key than the one recorded for the account
+        let file = write_wallet(&Pubkey::new_unique());
+        let mismatch = signer_from_path(
+            &format!("file://{path}"),
+            "test",
+            &mut Some(wallet_manager),
+            &config(),
+        );
+        std::fs::remove_file(&file).unwrap();
+        assert!(matches!(
+            mismatch,
+            Err(SignerSourceError::AccountKeyMismatch(name)) if name == "Ledger account"
+        ));
+    }
+}
@@ -0,0 +1,399 @@
+use {
+    crate::{
+        locator::{Locator, LocatorError},
+        remote_wallet::{
+            DeviceSelection, RemoteWallet, RemoteWalletError, RemoteWalletInfo, RemoteWalletManager,
+        },
+    },
+    serde::{Deserialize, Serialize},
+    sha2::{Digest, Sha256},
+    spacemesh_derivation_path::{DerivationPath, DerivationPathError},
+    spacemesh_signer::{OffchainMessage, Pubkey, Signature, Signer, SignerError, SoftwareKeypair},
+    spacemesh_transaction::{wallet, TransactionError},
+    thiserror::Error,
+};
+
+/// Descriptor version written by this crate
+/// cbindgen:ignore
+pub const DESCRIPTOR_VERSION: u32 = 1;
+
+/// Length of a seed fingerprint, in bytes
+const FINGERPRINT_LEN: usize = 4;
+
+/// Watch-only error
+#[derive(Error, Debug, Clone)]
+pub enum WatchOnlyError {
+    #[error("invalid watch-only descriptor: {0}")]
+    InvalidDescriptor(String),
+
+    #[error(
+        "unsupported watch-only descriptor version {0}, at most {DESCRIPTOR_VERSION} supported"
+    )]
+    UnsupportedVersion(u32),
+
+    #[error("address {address} of {path} doesn't match its public key")]
+    AddressMismatch { path: String, address: String },
+
+    #[error(transparent)]
+    DerivationPathError(#[from] DerivationPathError),
+
+    #[error(transparent)]
+    LocatorError(#[from] LocatorError),
+
+    #[error(transparent)]
+    RemoteWalletError(#[from] RemoteWalletError),
+
+    #[error(transparent)]
+    SignerError(#[from] SignerError),
+
+    #[error(transparent)]
+    TransactionError(#[from] TransactionError),
+}
+
+impl From<serde_json::Error> for WatchOnlyError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::InvalidDescriptor(err.to_string())
+    }
+}
+
+/// Where the keys of a watch-only descriptor are held
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Origin {
+    /// Keys derived from a seed, identified by its fingerprint, see `seed_fingerprint`
+    Seed { fingerprint: String },
+    /// Keys held by the hardware wallet at `locator`, which identifies it by its base pubkey
+    RemoteWallet { locator: String },
+}
+
+/// Public part of an account, enough to watch it without its secret key
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DescriptorAccount {
+    /// Absolute derivation path, e.g. `m/44'/540'/0'/0'/0'`
+    pub path: String,
+    /// Hex-encoded public key
+    pub public_key: String,
+    /// Bech32 address of the wallet account controlled by the key
+    pub address: String,
+}
+
+/// Watch-only descriptor, exported from a seed or a hardware wallet to let services such as
+/// accounting and monitoring follow accounts without access to their keys
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchOnlyDescriptor {
+    pub version: u32,
+    pub origin: Origin,
+    /// Human-readable part of the addresses
+    pub hrp: String,
+    pub accounts: Vec<DescriptorAccount>,
+}
+
+impl WatchOnlyDescriptor {
+    /// Descriptor of the keys derived from `seed` at `derivation_paths`
+    pub fn from_seed(
+        seed: &[u8],
+        derivation_paths: &[DerivationPath],
+        hrp: &str,
+    ) -> Result<Self, WatchOnlyError> {
+        let origin = Origin::Seed {
+            fingerprint: seed_fingerprint(seed)?,
+        };
+        let pubkeys = derivation_paths
+            .iter()
+            .map(|path| Ok(SoftwareKeypair::from_seed(seed, path.clone())?.pubkey()))
+            .collect::<Result<Vec<_>, WatchOnlyError>>()?;
+        Self::new(origin, derivation_paths, &pubkeys, hrp)
+    }
+
+    /// Descriptor of the keys at `derivation_paths` on `wallet`
+    pub fn from_remote_wallet(
+        wallet: &dyn RemoteWallet,
+        derivation_paths: &[DerivationPath],
+        hrp: &str,
+    ) -> Result<Self, WatchOnlyError> {
+        let locator = Locator {
+            manufacturer: wallet.manufacturer(),
+            pubkey: Some(wallet.get_base_pubkey()?),
+            ..Locator::default()
+        };
+        let origin = Origin::RemoteWallet {
+            locator: locator.to_string(),
+        };
+        let pubkeys = derivation_paths
+            .iter()
+            .map(|path| wallet.get_pubkey(path, false))
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(origin, derivation_paths, &pubkeys, hrp)
+    }
+
+    /// Descriptor of the keys at `derivation_paths` on the device matching `locator`. If several
+    /// devices match, `selection` decides which one is used.
+    pub fn from_wallet_manager(
+        locator: Locator,
+        wallet_manager: &RemoteWalletManager,
+        keypair_name: &str,
+        selection: &DeviceSelection,
+        derivation_paths: &[DerivationPath],
+        hrp: &str,
+    ) -> Result<Self, WatchOnlyError> {
+        let wallet = wallet_manager.get_wallet_from_info(
+            RemoteWalletInfo::parse_locator(locator),
+            keypair_name,
+            selection,
+        )?;
+        Self::from_remote_wallet(wallet.as_ref(), derivation_paths, hrp)
+    }
+
+    fn new(
+        origin: Origin,
+        derivation_paths: &[DerivationPath],
+        pubkeys: &[Pubkey],
+        hrp: &str,
+    ) -> Result<Self, WatchOnlyError> {
+        let accounts = derivation_paths
+            .iter()
+            .zip(pubkeys)
+            .map(|(path, pubkey)| {
+                Ok(DescriptorAccount {
+                    path: format!("{path:?}"),
+                    public_key: hex::encode(pubkey),
+                    address: wallet::principal(pubkey).encode(hrp)?,
+                })
+            })
+            .collect::<Result<_, WatchOnlyError>>()?;
+        Ok(Self {
+            version: DESCRIPTOR_VERSION,
+            origin,
+            hrp: hrp.to_string(),
+            accounts,
+        })
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, WatchOnlyError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> Result<String, WatchOnlyError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Fingerprint identifying a seed without revealing it: the first bytes of the SHA-256 hash of
+/// the pubkey at `DerivationPath::default()`, the key hardware wallets are identified by, in hex
+pub fn seed_fingerprint(seed: &[u8]) -> Result<String, WatchOnlyError> {
+    let pubkey = SoftwareKeypair::from_seed(seed, DerivationPath::default())?.pubkey();
+    Ok(hex::encode(&Sha256::digest(pubkey)[..FINGERPRINT_LEN]))
+}
+
+/// Read-only wallet imported from a watch-only descriptor. It can list addresses and verify
+/// signatures, but has no keys to sign with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchOnlyWallet {
+    origin: Origin,
+    hrp: String,
+    accounts: Vec<WatchOnlyAccount>,
+}
+
+/// Account of a watch-only wallet. It holds no key, so it can check signatures but not make them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchOnlyAccount {
+    pub derivation_path: DerivationPath,
+    pub pubkey: Pubkey,
+    pub address: String,
+}
+
+impl WatchOnlyWallet {
+    /// Import `descriptor`, checking that every address matches its public key, so that a
+    /// tampered descriptor can't make services watch the wrong address
+    pub fn import(descriptor: &WatchOnlyDescriptor) -> Result<Self, WatchOnlyError> {
+        if descriptor.version == 0 || descriptor.version > DESCRIPTOR_VERSION {
+            return Err(WatchOnlyError::UnsupportedVersion(descriptor.version));
+        }
+        if let Origin::RemoteWallet { locator } = &descriptor.origin {
+            Locator::new_from_path(locator)?;
+        }
+        let accounts = descriptor
+            .accounts
+            .iter()
+            .map(|account| {
+                let derivation_path = DerivationPath::from_absolute_path_str(&account.path)?;
+                let pubkey = hex::decode(&account.public_key)
+                    .ok()
+                    .and_then(|bytes| Pubkey::try_from(bytes.as_slice()).ok())
+                    .ok_or_else(|| {
+                        WatchOnlyError::InvalidDescriptor(format!(
+                            "invalid public key `{}`",
+                            account.public_key
+                        ))
+                    })?;
+                if wallet::principal(&pubkey).encode(&descriptor.hrp)? != account.address {
+                    return Err(WatchOnlyError::AddressMismatch {
+                        path: account.path.clone(),
+                        address: account.address.clone(),
+                    });
+                }
+                Ok(WatchOnlyAccount {
+                    derivation_path,
+                    pubkey,
+                    address: account.address.clone(),
+                })
+            })
+            .collect::<Result<_, WatchOnlyError>>()?;
+        Ok(Self {
+            origin: descriptor.origin.clone(),
+            hrp: descriptor.hrp.clone(),
+            accounts,
+        })
+    }
+
+    pub fn origin(&self) -> &Origin {
+        &self.origin
+    }
+
+    pub fn hrp(&self) -> &str {
+        &self.hrp
+    }
+
+    pub fn accounts(&self) -> &[WatchOnlyAccount] {
+        &self.accounts
+    }
+
+    pub fn addresses(&self) -> impl Iterator<Item = &str> {
+        self.accounts.iter().map(|account| account.address.as_str())
+    }
+
+    /// Account with address `address`
+    pub fn account(&self, address: &str) -> Option<&WatchOnlyAccount> {
+        self.accounts
+            .iter()
+            .find(|account| account.address == address)
+    }
+}
+
+impl WatchOnlyAccount {
+    /// Whether `signature` is a signature of `message` by this account
+    pub fn verify(&self, message: &[u8], signature: &Signature) -> bool {
+        signature.verify(&self.pubkey, message)
+    }
+
+    /// Whether `signature` is a signature of the off-chain message `message` by this account
+    pub fn verify_offchain_message(
+        &self,
+        message: &OffchainMessage,
+        signature: &Signature,
+    ) -> bool {
+        message.verify(&self.pubkey, signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::keystore::{mnemonic_to_seed, tests::MNEMONIC},
+        spacemesh_transaction::MAINNET_HRP,
+    };
+    #[cfg(feature = "hidapi")]
+    use {
+        crate::{
+            ledger::{tests::MockDevice, LedgerWallet},
+            locator::Manufacturer,
+        },
+        parking_lot::Mutex,
+        std::sync::Arc,
+    };
+
+    #[test]
+    fn test_export_import() {
+        let seed = mnemonic_to_seed(MNEMONIC, "").unwrap();
+        let paths: Vec<_> = (0..2)
+            .map(|account| DerivationPath::new_bip44(Some(account), Some(0)))
+            .collect();
+        let descriptor = WatchOnlyDescriptor::from_seed(&seed, &paths, MAINNET_HRP).unwrap();
+        assert_eq!(
+            descriptor.origin,
+            Origin::Seed {
+                fingerprint: seed_fingerprint(&seed).unwrap()
+            }
+        );
+        let descriptor = WatchOnlyDescriptor::from_json(&descriptor.to_json().unwrap()).unwrap();
+
+        let wallet = WatchOnlyWallet::import(&descriptor).unwrap();
+        let keypair = SoftwareKeypair::from_seed(&seed, paths[1].clone()).unwrap();
+        let address = wallet::principal(&keypair.pubkey())
+            .encode(MAINNET_HRP)
+            .unwrap();
+        assert_eq!(wallet.addresses().nth(1), Some(address.as_str()));
+
+        let account = wallet.account(&address).unwrap();
+        assert_eq!(account.derivation_path, paths[1]);
+        let signature = keypair.sign_message(b"message");
+        assert!(account.verify(b"message", &signature));
+        assert!(!wallet.accounts()[0].verify(b"message", &signature));
+        let message = OffchainMessage::new(b"message").unwrap();
+        assert!(account.verify_offchain_message(&message, &message.sign(&keypair).unwrap()));
+
+        let mut tampered = descriptor.clone();
+        tampered.accounts[0].address = address.clone();
+        match WatchOnlyWallet::import(&tampered) {
+            Err(WatchOnlyError::AddressMismatch { path, address: a }) => {
+                assert_eq!(path, tampered.accounts[0].path);
+                assert_eq!(a, address);
+            }
+            result => panic!("unexpected result {result:?}"),
+        }
+
+        let mut tampered = descriptor;
+        tampered.version = DESCRIPTOR_VERSION + 1;
+        assert!(WatchOnlyWallet::import(&tampered).is_err());
+    }
+
+    #[cfg(feature = "hidapi")]
+    #[test]
+    fn test_export_from_device() {
+        let device = MockDevice::new();
+        let device_pubkey = device.keypair.pubkey();
+        let usb = hidapi::HidApi::new_without_enumerate().unwrap();
+        let wallet_manager = RemoteWalletManager::new(Arc::new(Mutex::new(usb)));
+        wallet_manager.insert_device("/dev/ledger", Arc::new(LedgerWallet::new(device)));
+
+        let paths: Vec<_> = (0..2)
+            .map(|account| DerivationPath::new_bip44(Some(account), Some(0)))
+            .collect();
+        let descriptor = WatchOnlyDescriptor::from_wallet_manager(
+            Locator::new_from_path("usb://ledger").unwrap(),
+            &wallet_manager,
+            "test",
+            &DeviceSelection::FirstMatch,
+            &paths,
+            MAINNET_HRP,
+        )
+        .unwrap();
+
+        // the origin identifies the device by its base pubkey
+        let Origin::RemoteWallet { locator } = &descriptor.origin else {
+            panic!("unexpected origin {:?}", descriptor.origin);
+        };
+        let locator = Locator::new_from_path(locator).unwrap();
+        assert_eq!(locator.manufacturer, Manufacturer::Ledger);
+        assert_eq!(locator.pubkey, Some(device_pubkey));
+
+        // every account key was read from the device
+        let descriptor = WatchOnlyDescriptor::from_json(&descriptor.to_json().unwrap()).unwrap();
+        let wallet = WatchOnlyWallet::import(&descriptor).unwrap();
+        assert_eq!(wallet.origin(), &descriptor.origin);
+        assert_eq!(wallet.accounts().len(), paths.len());
+        for (account, path) in wallet.accounts().iter().zip(&paths) {
+            assert_eq!(&account.derivation_path, path);
+            assert_eq!(account.pubkey, device_pubkey);
+            assert_eq!(
+                account.address,
+                wallet::principal(&device_pubkey)
+                    .encode(MAINNET_HRP)
+                    .unwrap()
+            );
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use {
+    crate::{Pubkey, Signature, Signer, SignerError, PUBKEY_BYTES},
+    ed25519_dalek_bip32::{
+        ed25519_dalek::{self, Signer as _, KEYPAIR_LENGTH},
+        ExtendedSecretKey,
+    },
+    spacemesh_derivation_path::DerivationPath,
+    std::fmt,
+};
+
+/// Shortest seed keys are derived from
+const MIN_SEED_LEN: usize = 32;
+/// Purpose and coin type every Spacemesh key path starts with, see BIP-44
+const PURPOSE: u32 = 44;
+const COIN_TYPE: u32 = 540;
+const MIN_PATH_LEN: usize = 2;
+const MAX_PATH_LEN: usize = 5;
+
+/// Keypair derived from a BIP39 seed and held in memory. It signs the same way as a hardware
+/// wallet holding the same seed: the pubkey for a derivation path is the one a `RemoteKeypair`
+/// reports for that path.
+pub struct SoftwareKeypair {
+    keypair: ed25519_dalek::Keypair,
+    derivation_path: DerivationPath,
+}
+
+impl fmt::Debug for SoftwareKeypair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SoftwareKeypair")
+            .field("pubkey", &self.pubkey())
+            .field("derivation_path", &self.derivation_path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SoftwareKeypair {
+    /// Derive the key at `derivation_path` from a seed of at least 32 bytes. The path must start
+    /// with `m/44'/540'`, have at most 5 elements, and be fully hardened.
+    pub fn from_seed(seed: &[u8], derivation_path: DerivationPath) -> Result<Self, SignerError> {
+        if seed.len() < MIN_SEED_LEN {
+            return Err(SignerError::InvalidInput(format!(
+                "seed must be at least {MIN_SEED_LEN} bytes"
+            )));
+        }
+        check_derivation_path(&derivation_path)?;
+        let extended = ExtendedSecretKey::from_seed(seed)
+            .and_then(|extended| extended.derive(&derivation_path))
+            .map_err(|err| SignerError::InvalidInput(format!("failed to derive key: {err}")))?;
+        let keypair = ed25519_dalek::Keypair {
+            public: extended.public_key(),
+            secret: extended.secret_key,
+        };
+        Ok(Self {
+            keypair,
+            derivation_path,
+        })
+    }
+
+    pub fn derivation_path(&self) -> &DerivationPath {
+        &self.derivation_path
+    }
+
+    /// Secret key followed by the public key, as returned by `ed25519_bip32::derive_key`
+    pub fn to_bytes(&self) -> [u8; KEYPAIR_LENGTH] {
+        self.keypair.to_bytes()
+    }
+}
+
+/// Check that `derivation_path` follows the rules keys are derived under
+pub fn check_derivation_path(derivation_path: &DerivationPath) -> Result<(), SignerError> {
+    let path = derivation_path.path();
+    let invalid = |reason: &str| {
+        Err(SignerError::InvalidInput(format!(
+            "invalid derivation path {derivation_path:?}: {reason}"
+        )))
+    };
+    if path.len() < MIN_PATH_LEN {
+        return invalid("path too short");
+    }
+    if path.len() > MAX_PATH_LEN {
+        return invalid("path too long");
+    }
+    if path[0].to_u32() != PURPOSE {
+        return invalid("bad path purpose");
+    }
+    if path[1].to_u32() != COIN_TYPE {
+        return invalid("bad path coin type");
+    }
+    if !path.iter().all(|index| index.is_hardened()) {
+        return invalid("path isn't fully hardened");
+    }
+    Ok(())
+}
+
+impl Signer for SoftwareKeypair {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        let bytes: [u8; PUBKEY_BYTES] = self.keypair.public.to_bytes();
+        Ok(Pubkey::new_from_array(bytes))
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        Ok(Signature::from(self.keypair.sign(message).to_bytes()))
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, ed25519_dalek::Verifier};
+
+    const SEED: [u8; 64] = [7u8; 64];
+
+    #[test]
+    fn test_software_keypair() {
+        let path = DerivationPath::from_absolute_path_str("m/44'/540'/0'/0'/0'").unwrap();
+        let keypair = SoftwareKeypair::from_seed(&SEED, path).unwrap();
+        assert_eq!(
+            keypair.to_bytes()[..],
+            *ed25519_bip32::derive_key(&SEED, "m/44'/540'/0'/0'/0'")
+        );
+        assert_eq!(keypair.pubkey().as_ref(), &keypair.to_bytes()[32..]);
+        assert!(!keypair.is_interactive());
+
+        let signature = keypair.try_sign_message(b"message").unwrap();
+        let dalek_signature = ed25519_dalek::Signature::from_bytes(signature.as_ref()).unwrap();
+        assert!(keypair
+            .keypair
+            .public
+            .verify(b"message", &dalek_signature)
+            .is_ok());
+
+        let signer: &dyn Signer = &keypair;
+        assert_eq!(signer.pubkey(), keypair.pubkey());
+    }
+
+    #[test]
+    fn test_software_keypair_rules() {
+        let path = DerivationPath::default();
+        assert!(SoftwareKeypair::from_seed(&SEED[..31], path.clone()).is_err());
+        assert!(SoftwareKeypair::from_seed(&SEED, path).is_ok());
+
+        for path in [
+            "m/44'",
+            "m/44'/540'/0'/0'/0'/0'",
+            "m/43'/540'",
+            "m/44'/501'/0'",
+        ] {
+            let path = DerivationPath::from_absolute_path_str(path).unwrap();
+            assert!(SoftwareKeypair::from_seed(&SEED, path).is_err());
+        }
+    }
+}
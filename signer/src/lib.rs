@@ -0,0 +1,23 @@
+//! Spacemesh public keys, signatures and signers.
+//!
+//! Keys and signatures are Ed25519 and are rendered as lowercase hex. The `Signer` trait is
+//! implemented by every source of signatures, such as hardware wallets, so that callers don't
+//! need to know where a key lives. Off-chain messages are signed in their own domain, so that a
+//! message signature can't be replayed as a transaction signature.
+
+mod keypair;
+mod offchain_message;
+mod pubkey;
+mod signature;
+mod signer;
+
+pub use {
+    keypair::{check_derivation_path, SoftwareKeypair},
+    offchain_message::{
+        OffchainMessage, MAX_OFFCHAIN_MESSAGE_BYTES, OFFCHAIN_MESSAGE_DOMAIN,
+        OFFCHAIN_MESSAGE_PREFIX,
+    },
+    pubkey::{ParsePubkeyError, Pubkey, PUBKEY_BYTES},
+    signature::{ParseSignatureError, Signature, SIGNATURE_BYTES},
+    signer::{Signer, SignerError},
+};
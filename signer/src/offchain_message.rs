@@ -0,0 +1,83 @@
+use crate::{Pubkey, Signature, Signer, SignerError};
+
+/// First byte of every off-chain message signing body. Transaction signing bodies start with the
+/// genesis ID instead, so the two can't be confused.
+pub const OFFCHAIN_MESSAGE_DOMAIN: u8 = 0xff;
+/// Human-readable prefix, shown by devices that display what they sign
+pub const OFFCHAIN_MESSAGE_PREFIX: &[u8] = b"Spacemesh Signed Message:\n";
+/// Longest message that can be signed
+pub const MAX_OFFCHAIN_MESSAGE_BYTES: usize = 1 << 15;
+
+/// Message signed to prove ownership of a key, e.g. to an exchange. Its signature covers the
+/// signing domain, the prefix, the message length as a little-endian `u32`, and the message, so
+/// that it can never be replayed as a transaction signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OffchainMessage(Vec<u8>);
+
+impl OffchainMessage {
+    pub fn new(message: &[u8]) -> Result<Self, SignerError> {
+        if message.is_empty() {
+            return Err(SignerError::InvalidInput(
+                "off-chain message is empty".to_string(),
+            ));
+        }
+        if message.len() > MAX_OFFCHAIN_MESSAGE_BYTES {
+            return Err(SignerError::InvalidInput(format!(
+                "off-chain message is {} bytes, at most {MAX_OFFCHAIN_MESSAGE_BYTES} allowed",
+                message.len()
+            )));
+        }
+        Ok(Self(message.to_vec()))
+    }
+
+    pub fn message(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Bytes the signature covers
+    pub fn signing_body(&self) -> Vec<u8> {
+        let mut body = Vec::with_capacity(1 + OFFCHAIN_MESSAGE_PREFIX.len() + 4 + self.0.len());
+        body.push(OFFCHAIN_MESSAGE_DOMAIN);
+        body.extend_from_slice(OFFCHAIN_MESSAGE_PREFIX);
+        body.extend_from_slice(&(self.0.len() as u32).to_le_bytes());
+        body.extend_from_slice(&self.0);
+        body
+    }
+
+    pub fn sign(&self, signer: &dyn Signer) -> Result<Signature, SignerError> {
+        signer.try_sign_message(&self.signing_body())
+    }
+
+    /// Whether `signature` is a signature of this message by `pubkey`
+    pub fn verify(&self, pubkey: &Pubkey, signature: &Signature) -> bool {
+        signature.verify(pubkey, &self.signing_body())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::SoftwareKeypair, spacemesh_derivation_path::DerivationPath};
+
+    #[test]
+    fn test_offchain_message() {
+        let keypair = SoftwareKeypair::from_seed(&[2u8; 64], DerivationPath::default()).unwrap();
+        let message = OffchainMessage::new(b"I own this address").unwrap();
+
+        let body = message.signing_body();
+        assert_eq!(body[0], OFFCHAIN_MESSAGE_DOMAIN);
+        assert_eq!(&body[1..27], OFFCHAIN_MESSAGE_PREFIX);
+        assert_eq!(&body[27..31], &[18, 0, 0, 0]);
+        assert_eq!(&body[31..], b"I own this address");
+
+        let signature = message.sign(&keypair).unwrap();
+        assert!(message.verify(&keypair.pubkey(), &signature));
+        assert!(!OffchainMessage::new(b"I own this addresS")
+            .unwrap()
+            .verify(&keypair.pubkey(), &signature));
+        // a signature of the raw bytes doesn't verify as a message signature
+        assert!(!message.verify(&keypair.pubkey(), &keypair.sign_message(message.message())));
+
+        assert!(OffchainMessage::new(b"").is_err());
+        assert!(OffchainMessage::new(&[0u8; MAX_OFFCHAIN_MESSAGE_BYTES + 1]).is_err());
+    }
+}
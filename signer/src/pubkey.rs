@@ -0,0 +1,136 @@
+use {
+    std::{
+        convert::TryFrom,
+        fmt,
+        str::FromStr,
+        sync::atomic::{AtomicU64, Ordering},
+    },
+    thiserror::Error,
+};
+
+/// Number of bytes in a public key
+pub const PUBKEY_BYTES: usize = 32;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParsePubkeyError {
+    #[error("string decoded to wrong size for pubkey")]
+    WrongSize,
+    #[error("invalid hex string")]
+    Invalid,
+}
+
+/// Ed25519 public key
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Pubkey([u8; PUBKEY_BYTES]);
+
+impl Pubkey {
+    pub const fn new_from_array(bytes: [u8; PUBKEY_BYTES]) -> Self {
+        Self(bytes)
+    }
+
+    pub const fn to_bytes(self) -> [u8; PUBKEY_BYTES] {
+        self.0
+    }
+
+    /// Unique, non-cryptographic key, for tests
+    pub fn new_unique() -> Self {
+        static COUNTER: AtomicU64 = AtomicU64::new(1);
+        let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let mut bytes = [0u8; PUBKEY_BYTES];
+        bytes[..8].copy_from_slice(&count.to_be_bytes());
+        Self(bytes)
+    }
+}
+
+impl AsRef<[u8]> for Pubkey {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<[u8; PUBKEY_BYTES]> for Pubkey {
+    fn from(bytes: [u8; PUBKEY_BYTES]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl TryFrom<&[u8]> for Pubkey {
+    type Error = ParsePubkeyError;
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        <[u8; PUBKEY_BYTES]>::try_from(bytes)
+            .map(Self)
+            .map_err(|_| ParsePubkeyError::WrongSize)
+    }
+}
+
+impl TryFrom<Vec<u8>> for Pubkey {
+    type Error = ParsePubkeyError;
+    fn try_from(bytes: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(bytes.as_slice())
+    }
+}
+
+impl FromStr for Pubkey {
+    type Err = ParsePubkeyError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = hex::decode(s).map_err(|_| ParsePubkeyError::Invalid)?;
+        Self::try_from(bytes)
+    }
+}
+
+impl TryFrom<&str> for Pubkey {
+    type Error = ParsePubkeyError;
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        Self::from_str(s)
+    }
+}
+
+impl fmt::Display for Pubkey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+impl fmt::Debug for Pubkey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+#[cfg(feature = "solana")]
+impl From<Pubkey> for solana_sdk::pubkey::Pubkey {
+    fn from(pubkey: Pubkey) -> Self {
+        Self::new_from_array(pubkey.0)
+    }
+}
+
+#[cfg(feature = "solana")]
+impl From<solana_sdk::pubkey::Pubkey> for Pubkey {
+    fn from(pubkey: solana_sdk::pubkey::Pubkey) -> Self {
+        Self(pubkey.to_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pubkey_str() {
+        let pubkey = Pubkey::new_unique();
+        assert_eq!(pubkey.to_string().len(), 2 * PUBKEY_BYTES);
+        assert_eq!(pubkey.to_string().parse(), Ok(pubkey));
+        assert_eq!(format!("{pubkey:?}"), pubkey.to_string());
+        assert_ne!(Pubkey::new_unique(), pubkey);
+
+        assert_eq!(
+            "bad-pubkey".parse::<Pubkey>(),
+            Err(ParsePubkeyError::Invalid)
+        );
+        assert_eq!("00ff".parse::<Pubkey>(), Err(ParsePubkeyError::WrongSize));
+        assert_eq!(
+            Pubkey::try_from(&[0u8; 31][..]),
+            Err(ParsePubkeyError::WrongSize)
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use {
+    crate::Pubkey,
+    ed25519_dalek_bip32::ed25519_dalek::{self, Verifier},
+    std::{convert::TryFrom, fmt, str::FromStr},
+    thiserror::Error,
+};
+
+/// Number of bytes in a signature
+pub const SIGNATURE_BYTES: usize = 64;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseSignatureError {
+    #[error("string decoded to wrong size for signature")]
+    WrongSize,
+    #[error("invalid hex string")]
+    Invalid,
+}
+
+/// Ed25519 signature
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Signature([u8; SIGNATURE_BYTES]);
+
+impl Default for Signature {
+    fn default() -> Self {
+        Self([0u8; SIGNATURE_BYTES])
+    }
+}
+
+impl Signature {
+    pub const fn to_bytes(self) -> [u8; SIGNATURE_BYTES] {
+        self.0
+    }
+
+    /// Whether this is a valid signature of `message` by `pubkey`
+    pub fn verify(&self, pubkey: &Pubkey, message: &[u8]) -> bool {
+        let Ok(pubkey) = ed25519_dalek::PublicKey::from_bytes(pubkey.as_ref()) else {
+            return false;
+        };
+        let Ok(signature) = ed25519_dalek::Signature::from_bytes(&self.0) else {
+            return false;
+        };
+        pubkey.verify(message, &signature).is_ok()
+    }
+}
+
+impl AsRef<[u8]> for Signature {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<[u8; SIGNATURE_BYTES]> for Signature {
+    fn from(bytes: [u8; SIGNATURE_BYTES]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl TryFrom<&[u8]> for Signature {
+    type Error = ParseSignatureError;
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        <[u8; SIGNATURE_BYTES]>::try_from(bytes)
+            .map(Self)
+            .map_err(|_| ParseSignatureError::WrongSize)
+    }
+}
+
+impl TryFrom<Vec<u8>> for Signature {
+    type Error = ParseSignatureError;
+    fn try_from(bytes: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(bytes.as_slice())
+    }
+}
+
+impl FromStr for Signature {
+    type Err = ParseSignatureError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = hex::decode(s).map_err(|_| ParseSignatureError::Invalid)?;
+        Self::try_from(bytes)
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+impl fmt::Debug for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+#[cfg(feature = "solana")]
+impl From<Signature> for solana_sdk::signature::Signature {
+    fn from(signature: Signature) -> Self {
+        Self::from(signature.0)
+    }
+}
+
+#[cfg(feature = "solana")]
+impl From<solana_sdk::signature::Signature> for Signature {
+    fn from(signature: solana_sdk::signature::Signature) -> Self {
+        let bytes: [u8; SIGNATURE_BYTES] = signature.into();
+        Self(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{Signer, SoftwareKeypair},
+        spacemesh_derivation_path::DerivationPath,
+    };
+
+    #[test]
+    fn test_signature_str() {
+        let signature = Signature::from([0xab; SIGNATURE_BYTES]);
+        assert_eq!(signature.to_string(), "ab".repeat(SIGNATURE_BYTES));
+        assert_eq!(signature.to_string().parse(), Ok(signature));
+        assert_eq!(
+            "abab".parse::<Signature>(),
+            Err(ParseSignatureError::WrongSize)
+        );
+        assert_eq!(
+            "xyz".parse::<Signature>(),
+            Err(ParseSignatureError::Invalid)
+        );
+    }
+
+    #[test]
+    fn test_verify() {
+        let keypair = SoftwareKeypair::from_seed(&[1u8; 64], DerivationPath::default()).unwrap();
+        let signature = keypair.sign_message(b"message");
+        assert!(signature.verify(&keypair.pubkey(), b"message"));
+        assert!(!signature.verify(&keypair.pubkey(), b"other message"));
+        assert!(!signature.verify(&Pubkey::new_unique(), b"message"));
+        assert!(!Signature::default().verify(&keypair.pubkey(), b"message"));
+    }
+}
@@ -0,0 +1,68 @@
+use {
+    crate::{Pubkey, Signature},
+    thiserror::Error,
+};
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SignerError {
+    #[error("keypair-pubkey mismatch")]
+    KeypairPubkeyMismatch,
+
+    #[error("custom error: {0}")]
+    Custom(String),
+
+    #[error("invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("no device found")]
+    NoDeviceFound,
+
+    #[error("device protocol error: {0}")]
+    Protocol(String),
+
+    #[error("connection error: {0}")]
+    Connection(String),
+
+    #[error("operation has been cancelled: {0}")]
+    UserCancel(String),
+
+    #[error("signature does not verify against the signer's pubkey")]
+    InvalidSignature,
+}
+
+/// Source of signatures for a single key, such as a hardware wallet
+pub trait Signer {
+    /// Infallibly gets the public key, returning the default pubkey on failure
+    fn pubkey(&self) -> Pubkey {
+        self.try_pubkey().unwrap_or_default()
+    }
+
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError>;
+
+    /// Infallibly signs `message`, returning the default signature on failure
+    fn sign_message(&self, message: &[u8]) -> Signature {
+        self.try_sign_message(message).unwrap_or_default()
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError>;
+
+    /// Whether signing requires user interaction, e.g. confirming on a device
+    fn is_interactive(&self) -> bool;
+}
+
+#[cfg(feature = "solana")]
+impl From<SignerError> for solana_sdk::signer::SignerError {
+    fn from(err: SignerError) -> Self {
+        use solana_sdk::signer::SignerError as SolanaSignerError;
+        match err {
+            SignerError::KeypairPubkeyMismatch => SolanaSignerError::KeypairPubkeyMismatch,
+            SignerError::Custom(e) => SolanaSignerError::Custom(e),
+            SignerError::InvalidInput(e) => SolanaSignerError::InvalidInput(e),
+            SignerError::NoDeviceFound => SolanaSignerError::NoDeviceFound,
+            SignerError::Protocol(e) => SolanaSignerError::Protocol(e),
+            SignerError::Connection(e) => SolanaSignerError::Connection(e),
+            SignerError::UserCancel(e) => SolanaSignerError::UserCancel(e),
+            SignerError::InvalidSignature => SolanaSignerError::Protocol(err.to_string()),
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use {
+    crate::TransactionError,
+    bech32::{Bech32, Hrp},
+    std::fmt,
+};
+
+/// Number of bytes in an address
+pub const ADDRESS_BYTES: usize = 24;
+/// Leading address bytes, which are always zero
+const RESERVED_BYTES: usize = 4;
+
+/// Human-readable part of mainnet addresses
+pub const MAINNET_HRP: &str = "sm";
+/// Human-readable part of testnet addresses
+pub const TESTNET_HRP: &str = "stest";
+
+/// Account address: four zero bytes followed by the last 20 bytes of a hash, rendered as bech32
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Address([u8; ADDRESS_BYTES]);
+
+impl Address {
+    pub const fn new_from_array(bytes: [u8; ADDRESS_BYTES]) -> Self {
+        Self(bytes)
+    }
+
+    pub const fn to_bytes(self) -> [u8; ADDRESS_BYTES] {
+        self.0
+    }
+
+    /// Address of a 32-byte hash, such as the principal hash of an account
+    pub(crate) fn from_hash(hash: &[u8; 32]) -> Self {
+        let mut bytes = [0u8; ADDRESS_BYTES];
+        bytes[RESERVED_BYTES..].copy_from_slice(&hash[32 - (ADDRESS_BYTES - RESERVED_BYTES)..]);
+        Self(bytes)
+    }
+
+    /// Render the address as bech32 with human-readable part `hrp`, e.g. `sm1qqqqqq...`
+    pub fn encode(&self, hrp: &str) -> Result<String, TransactionError> {
+        let hrp = Hrp::parse(hrp).map_err(|e| TransactionError::InvalidAddress(e.to_string()))?;
+        bech32::encode::<Bech32>(hrp, &self.0)
+            .map_err(|e| TransactionError::InvalidAddress(e.to_string()))
+    }
+
+    /// Parse a bech32 address, returning its human-readable part and the address
+    pub fn decode(address: &str) -> Result<(String, Self), TransactionError> {
+        let (hrp, data) =
+            bech32::decode(address).map_err(|e| TransactionError::InvalidAddress(e.to_string()))?;
+        let address = Self::try_from(data.as_slice())?;
+        Ok((hrp.to_lowercase(), address))
+    }
+}
+
+impl AsRef<[u8]> for Address {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<[u8; ADDRESS_BYTES]> for Address {
+    fn from(bytes: [u8; ADDRESS_BYTES]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl TryFrom<&[u8]> for Address {
+    type Error = TransactionError;
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let bytes = <[u8; ADDRESS_BYTES]>::try_from(bytes).map_err(|_| {
+            TransactionError::InvalidAddress(format!(
+                "expected {ADDRESS_BYTES} bytes, got {}",
+                bytes.len()
+            ))
+        })?;
+        if bytes[..RESERVED_BYTES].iter().any(|b| *b != 0) {
+            return Err(TransactionError::InvalidAddress(
+                "reserved bytes must be zero".to_string(),
+            ));
+        }
+        Ok(Self(bytes))
+    }
+}
+
+impl fmt::Debug for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Address({})", hex::encode(self.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_address_bech32() {
+        let address = Address::from_hash(&[7u8; 32]);
+        assert_eq!(
+            &address.to_bytes()[..RESERVED_BYTES],
+            &[0u8; RESERVED_BYTES]
+        );
+
+        let encoded = address.encode(MAINNET_HRP).unwrap();
+        assert!(encoded.starts_with("sm1qqqqq"));
+        assert_eq!(
+            Address::decode(&encoded).unwrap(),
+            (MAINNET_HRP.to_string(), address)
+        );
+        assert_eq!(
+            Address::decode(&encoded.to_uppercase()).unwrap(),
+            (MAINNET_HRP.to_string(), address)
+        );
+
+        let mut corrupted = encoded.into_bytes();
+        let last = corrupted.len() - 1;
+        corrupted[last] = if corrupted[last] == b'q' { b'p' } else { b'q' };
+        assert!(Address::decode(std::str::from_utf8(&corrupted).unwrap()).is_err());
+
+        let reserved = bech32::encode::<Bech32>(Hrp::parse("sm").unwrap(), &[1u8; 24]).unwrap();
+        assert!(Address::decode(&reserved).is_err());
+    }
+}
@@ -0,0 +1,33 @@
+use {spacemesh_signer::SignerError, thiserror::Error};
+
+/// Transaction error
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum TransactionError {
+    #[error("invalid address: {0}")]
+    InvalidAddress(String),
+
+    #[error("invalid {field}: expected {expected} bytes, got {actual}")]
+    InvalidLength {
+        field: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+
+    #[error("invalid key set: {0}")]
+    InvalidKeySet(String),
+
+    #[error("signer index {index} out of range for {keys} keys")]
+    InvalidSignerIndex { index: u8, keys: usize },
+
+    #[error("signer {0} does not hold the key at its index")]
+    SignerKeyMismatch(u8),
+
+    #[error("invalid signature from signer {0}")]
+    InvalidSignature(u8),
+
+    #[error("{collected} of {required} required signatures collected")]
+    NotEnoughSignatures { required: u8, collected: usize },
+
+    #[error(transparent)]
+    SignerError(#[from] SignerError),
+}
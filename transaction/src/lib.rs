@@ -0,0 +1,357 @@
+//! Spacemesh transaction encoding and signing.
+//!
+//! Transactions are built for a template, such as the single-signature `wallet` or `multisig`, and
+//! encoded in canonical SCALE form. The signature covers the genesis ID followed by the encoded
+//! transaction, and is appended to it to produce a transaction ready to be submitted to a node.
+//! Multisig transactions collect one signature per required key instead. The wallet builders are
+//! also exposed to C and, through wasm-bindgen, to JavaScript.
+
+mod address;
+mod error;
+pub mod multisig;
+mod scale;
+mod transaction;
+pub mod wallet;
+
+pub use {
+    address::{Address, ADDRESS_BYTES, MAINNET_HRP, TESTNET_HRP},
+    error::TransactionError,
+    transaction::{
+        signing_body, GenesisId, Payload, SignedTransaction, Transaction, GENESIS_ID_BYTES,
+    },
+};
+use {
+    spacemesh_signer::{Pubkey, Signature, PUBKEY_BYTES, SIGNATURE_BYTES},
+    std::ffi::{c_char, CStr},
+    wasm_bindgen::prelude::*,
+};
+
+/// Address of the account spawned from `template` with the encoded spawn `arguments`
+pub(crate) fn compute_principal(template: &Address, arguments: &[u8]) -> Address {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(template.as_ref());
+    hasher.update(arguments);
+    Address::from_hash(hasher.finalize().as_bytes())
+}
+
+fn to_array<const N: usize>(
+    field: &'static str,
+    bytes: &[u8],
+) -> Result<[u8; N], TransactionError> {
+    bytes
+        .try_into()
+        .map_err(|_| TransactionError::InvalidLength {
+            field,
+            expected: N,
+            actual: bytes.len(),
+        })
+}
+
+fn to_address(field: &'static str, bytes: &[u8]) -> Result<Address, TransactionError> {
+    Address::try_from(&to_array::<ADDRESS_BYTES>(field, bytes)?[..])
+}
+
+#[wasm_bindgen]
+/// wallet_principal returns the 24-byte address of the wallet account controlled by the 32-byte
+/// public_key.
+pub fn wallet_principal(public_key: &[u8]) -> Result<Box<[u8]>, JsError> {
+    let public_key = Pubkey::from(to_array::<PUBKEY_BYTES>("public key", public_key)?);
+    Ok(Box::new(wallet::principal(&public_key).to_bytes()))
+}
+
+#[wasm_bindgen]
+/// wallet_self_spawn returns the unsigned transaction spawning the wallet account controlled by
+/// public_key, paid by the account itself.
+pub fn wallet_self_spawn(
+    public_key: &[u8],
+    nonce: u64,
+    gas_price: u64,
+) -> Result<Box<[u8]>, JsError> {
+    let args = wallet::SpawnArguments {
+        public_key: to_array::<PUBKEY_BYTES>("public key", public_key)?.into(),
+    };
+    let tx = wallet::self_spawn(&args, &Payload { nonce, gas_price });
+    Ok(tx.as_bytes().into())
+}
+
+#[wasm_bindgen]
+/// wallet_spawn returns the unsigned transaction spawning the wallet account controlled by
+/// public_key, paid by the 24-byte principal.
+pub fn wallet_spawn(
+    principal: &[u8],
+    public_key: &[u8],
+    nonce: u64,
+    gas_price: u64,
+) -> Result<Box<[u8]>, JsError> {
+    let principal = to_address("principal", principal)?;
+    let args = wallet::SpawnArguments {
+        public_key: to_array::<PUBKEY_BYTES>("public key", public_key)?.into(),
+    };
+    let tx = wallet::spawn(&principal, &args, &Payload { nonce, gas_price });
+    Ok(tx.as_bytes().into())
+}
+
+#[wasm_bindgen]
+/// wallet_spend returns the unsigned transaction transferring amount from the wallet account at
+/// the 24-byte principal to the 24-byte destination.
+pub fn wallet_spend(
+    principal: &[u8],
+    destination: &[u8],
+    amount: u64,
+    nonce: u64,
+    gas_price: u64,
+) -> Result<Box<[u8]>, JsError> {
+    let principal = to_address("principal", principal)?;
+    let args = wallet::SpendArguments {
+        destination: to_address("destination", destination)?,
+        amount,
+    };
+    let tx = wallet::spend(&principal, &args, &Payload { nonce, gas_price });
+    Ok(tx.as_bytes().into())
+}
+
+#[wasm_bindgen(js_name = signing_body)]
+/// signing_body returns the bytes to sign for the unsigned transaction tx on the network with the
+/// 20-byte genesis_id.
+pub fn signing_body_wasm(genesis_id: &[u8], tx: &[u8]) -> Result<Box<[u8]>, JsError> {
+    let genesis_id = to_array::<GENESIS_ID_BYTES>("genesis ID", genesis_id)?;
+    Ok(signing_body(&genesis_id, tx).into())
+}
+
+#[wasm_bindgen]
+/// attach_signature appends the 64-byte signature to the unsigned transaction tx, returning a
+/// transaction ready to be submitted.
+pub fn attach_signature(tx: &[u8], signature: &[u8]) -> Result<Box<[u8]>, JsError> {
+    Ok(_attach_signature(tx, signature)?.into())
+}
+
+fn _attach_signature(tx: &[u8], signature: &[u8]) -> Result<Vec<u8>, TransactionError> {
+    let signature = Signature::from(to_array::<SIGNATURE_BYTES>("signature", signature)?);
+    Ok(Transaction::from_bytes(tx.to_vec())
+        .with_signature(&signature)
+        .into_bytes())
+}
+
+#[wasm_bindgen]
+/// encode_address renders the 24-byte address as bech32 with human-readable part hrp, e.g. "sm".
+pub fn encode_address(address: &[u8], hrp: &str) -> Result<String, JsError> {
+    Ok(to_address("address", address)?.encode(hrp)?)
+}
+
+#[wasm_bindgen]
+/// decode_address parses a bech32 address, returning its 24 bytes.
+pub fn decode_address(address: &str) -> Result<Box<[u8]>, JsError> {
+    let (_hrp, address) = Address::decode(address)?;
+    Ok(Box::new(address.to_bytes()))
+}
+
+/// wallet_principal_c writes the 24-byte address of the wallet account controlled by the 32-byte
+/// public_key to result, which must be at least 24 bytes long. It returns a status code, with a
+/// return value of zero indicating success.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn wallet_principal_c(public_key: *const u8, result: *mut u8) -> u16 {
+    let public_key = Pubkey::from(unsafe { read_array::<PUBKEY_BYTES>(public_key) });
+    let result_slice = unsafe { std::slice::from_raw_parts_mut(result, ADDRESS_BYTES) };
+    result_slice.copy_from_slice(wallet::principal(&public_key).as_ref());
+    0
+}
+
+/// wallet_self_spawn_c writes the unsigned transaction spawning the wallet account controlled by
+/// the 32-byte public_key, paid by the account itself, to result. result_cap is the size of
+/// result; the length of the transaction is written to result_len. It returns a status code, with
+/// a return value of zero indicating success.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn wallet_self_spawn_c(
+    public_key: *const u8,
+    nonce: u64,
+    gas_price: u64,
+    result: *mut u8,
+    result_cap: usize,
+    result_len: *mut usize,
+) -> u16 {
+    let args = wallet::SpawnArguments {
+        public_key: unsafe { read_array::<PUBKEY_BYTES>(public_key) }.into(),
+    };
+    let tx = wallet::self_spawn(&args, &Payload { nonce, gas_price });
+    write_result(Ok(tx.as_bytes().to_vec()), result, result_cap, result_len)
+}
+
+/// wallet_spawn_c writes the unsigned transaction spawning the wallet account controlled by the
+/// 32-byte public_key, paid by the 24-byte principal, to result. result_cap is the size of
+/// result; the length of the transaction is written to result_len. It returns a status code, with
+/// a return value of zero indicating success.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn wallet_spawn_c(
+    principal: *const u8,
+    public_key: *const u8,
+    nonce: u64,
+    gas_price: u64,
+    result: *mut u8,
+    result_cap: usize,
+    result_len: *mut usize,
+) -> u16 {
+    let principal = unsafe { std::slice::from_raw_parts(principal, ADDRESS_BYTES) };
+    let args = wallet::SpawnArguments {
+        public_key: unsafe { read_array::<PUBKEY_BYTES>(public_key) }.into(),
+    };
+    let tx = to_address("principal", principal).map(|principal| {
+        wallet::spawn(&principal, &args, &Payload { nonce, gas_price })
+            .as_bytes()
+            .to_vec()
+    });
+    write_result(tx, result, result_cap, result_len)
+}
+
+/// wallet_spend_c writes the unsigned transaction transferring amount from the wallet account at
+/// the 24-byte principal to the 24-byte destination to result. result_cap is the size of result;
+/// the length of the transaction is written to result_len. It returns a status code, with a
+/// return value of zero indicating success.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn wallet_spend_c(
+    principal: *const u8,
+    destination: *const u8,
+    amount: u64,
+    nonce: u64,
+    gas_price: u64,
+    result: *mut u8,
+    result_cap: usize,
+    result_len: *mut usize,
+) -> u16 {
+    let principal = unsafe { std::slice::from_raw_parts(principal, ADDRESS_BYTES) };
+    let destination = unsafe { std::slice::from_raw_parts(destination, ADDRESS_BYTES) };
+    let tx = (|| {
+        let principal = to_address("principal", principal)?;
+        let args = wallet::SpendArguments {
+            destination: to_address("destination", destination)?,
+            amount,
+        };
+        let tx = wallet::spend(&principal, &args, &Payload { nonce, gas_price });
+        Ok(tx.as_bytes().to_vec())
+    })();
+    write_result(tx, result, result_cap, result_len)
+}
+
+/// signing_body_c writes the bytes to sign for the unsigned transaction tx on the network with
+/// the 20-byte genesis_id to result. result_cap is the size of result; the length of the signing
+/// body is written to result_len. It returns a status code, with a return value of zero
+/// indicating success.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn signing_body_c(
+    genesis_id: *const u8,
+    tx: *const u8,
+    tx_len: usize,
+    result: *mut u8,
+    result_cap: usize,
+    result_len: *mut usize,
+) -> u16 {
+    let genesis_id = unsafe { read_array::<GENESIS_ID_BYTES>(genesis_id) };
+    let tx = unsafe { std::slice::from_raw_parts(tx, tx_len) };
+    let body = Ok(signing_body(&genesis_id, tx));
+    write_result(body, result, result_cap, result_len)
+}
+
+/// attach_signature_c writes the unsigned transaction tx followed by the 64-byte signature to
+/// result, producing a transaction ready to be submitted. result_cap is the size of result; the
+/// length of the signed transaction is written to result_len. It returns a status code, with a
+/// return value of zero indicating success.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn attach_signature_c(
+    tx: *const u8,
+    tx_len: usize,
+    signature: *const u8,
+    result: *mut u8,
+    result_cap: usize,
+    result_len: *mut usize,
+) -> u16 {
+    let tx = unsafe { std::slice::from_raw_parts(tx, tx_len) };
+    let signature = unsafe { std::slice::from_raw_parts(signature, SIGNATURE_BYTES) };
+    write_result(
+        _attach_signature(tx, signature),
+        result,
+        result_cap,
+        result_len,
+    )
+}
+
+/// encode_address_c writes the 24-byte address, rendered as bech32 with human-readable part hrp,
+/// to result as a NUL-terminated string. result_cap is the size of result. It returns a status
+/// code, with a return value of zero indicating success.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn encode_address_c(
+    address: *const u8,
+    hrp: *const c_char,
+    result: *mut c_char,
+    result_cap: usize,
+) -> u16 {
+    let address = unsafe { std::slice::from_raw_parts(address, ADDRESS_BYTES) };
+    let hrp = unsafe { CStr::from_ptr(hrp) };
+    let encoded = hrp
+        .to_str()
+        .map_err(|e| TransactionError::InvalidAddress(format!("converting hrp string: {e}")))
+        .and_then(|hrp| to_address("address", address)?.encode(hrp))
+        .map(|encoded| {
+            let mut bytes = encoded.into_bytes();
+            bytes.push(0);
+            bytes
+        });
+    let mut result_len = 0;
+    write_result(encoded, result as *mut u8, result_cap, &mut result_len)
+}
+
+/// decode_address_c parses the NUL-terminated bech32 address and writes its 24 bytes to result,
+/// which must be at least 24 bytes long. It returns a status code, with a return value of zero
+/// indicating success.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn decode_address_c(address: *const c_char, result: *mut u8) -> u16 {
+    let address = unsafe { CStr::from_ptr(address) };
+    let decoded = address
+        .to_str()
+        .map_err(|e| TransactionError::InvalidAddress(format!("converting address string: {e}")))
+        .and_then(Address::decode)
+        .map(|(_hrp, address)| address.to_bytes().to_vec());
+    let mut result_len = 0;
+    write_result(decoded, result, ADDRESS_BYTES, &mut result_len)
+}
+
+/// Copy the `N` bytes at `ptr` to an array
+unsafe fn read_array<const N: usize>(ptr: *const u8) -> [u8; N] {
+    let mut array = [0u8; N];
+    array.copy_from_slice(std::slice::from_raw_parts(ptr, N));
+    array
+}
+
+/// Copy `bytes` to the caller's buffer, or print the error, returning the status code
+fn write_result(
+    bytes: Result<Vec<u8>, TransactionError>,
+    result: *mut u8,
+    result_cap: usize,
+    result_len: *mut usize,
+) -> u16 {
+    match bytes {
+        Ok(bytes) if bytes.len() <= result_cap => {
+            let result_slice = unsafe { std::slice::from_raw_parts_mut(result, bytes.len()) };
+            result_slice.copy_from_slice(&bytes);
+            unsafe { *result_len = bytes.len() };
+            0
+        }
+        Ok(bytes) => {
+            eprintln!(
+                "result buffer too small: need {} bytes, got {result_cap}",
+                bytes.len()
+            );
+            1
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            1
+        }
+    }
+}
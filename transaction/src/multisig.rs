@@ -0,0 +1,298 @@
+//! Transactions for the multisig template, which requires signatures from `required` of its keys
+
+use {
+    crate::{
+        scale,
+        transaction::{method, GenesisId, Payload, SignedTransaction, Transaction},
+        wallet::SpendArguments,
+        Address, TransactionError, ADDRESS_BYTES,
+    },
+    spacemesh_signer::{Pubkey, Signature, Signer},
+    std::collections::BTreeMap,
+};
+
+/// Address of the multisig template
+pub const TEMPLATE_ADDRESS: Address = Address::new_from_array({
+    let mut bytes = [0u8; ADDRESS_BYTES];
+    bytes[ADDRESS_BYTES - 1] = 2;
+    bytes
+});
+
+/// Largest number of keys in a multisig account
+pub const MAX_KEYS: usize = 10;
+
+/// Arguments of the spawn method: the keys that control the account, and how many of them must
+/// sign each transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpawnArguments {
+    required: u8,
+    public_keys: Vec<Pubkey>,
+}
+
+impl SpawnArguments {
+    /// Key set requiring `required` signatures out of `public_keys`. Key order matters: it
+    /// determines the account address and the index of each signer.
+    pub fn new(required: u8, public_keys: Vec<Pubkey>) -> Result<Self, TransactionError> {
+        if public_keys.is_empty() || public_keys.len() > MAX_KEYS {
+            return Err(TransactionError::InvalidKeySet(format!(
+                "expected 1 to {MAX_KEYS} keys, got {}",
+                public_keys.len()
+            )));
+        }
+        if required == 0 || required as usize > public_keys.len() {
+            return Err(TransactionError::InvalidKeySet(format!(
+                "cannot require {required} of {} signatures",
+                public_keys.len()
+            )));
+        }
+        Ok(Self {
+            required,
+            public_keys,
+        })
+    }
+
+    pub fn required(&self) -> u8 {
+        self.required
+    }
+
+    pub fn public_keys(&self) -> &[Pubkey] {
+        &self.public_keys
+    }
+
+    /// Address of the multisig account controlled by this key set
+    pub fn principal(&self) -> Address {
+        crate::compute_principal(&TEMPLATE_ADDRESS, &self.encode())
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut out = vec![];
+        scale::encode_compact(&mut out, self.required.into());
+        scale::encode_compact(&mut out, self.public_keys.len() as u64);
+        for public_key in &self.public_keys {
+            out.extend_from_slice(public_key.as_ref());
+        }
+        out
+    }
+}
+
+/// Spawn the multisig account controlled by `args`, paid by the account itself. The account must
+/// have been funded first.
+pub fn self_spawn(args: &SpawnArguments, payload: &Payload) -> Transaction {
+    spawn(&args.principal(), args, payload)
+}
+
+/// Spawn the multisig account controlled by `args`, paid by `principal`
+pub fn spawn(principal: &Address, args: &SpawnArguments, payload: &Payload) -> Transaction {
+    Transaction::encode(
+        principal,
+        method::SPAWN,
+        Some(&TEMPLATE_ADDRESS),
+        payload,
+        &args.encode(),
+    )
+}
+
+/// Transfer `args.amount` from the multisig account at `principal` to `args.destination`
+pub fn spend(principal: &Address, args: &SpendArguments, payload: &Payload) -> Transaction {
+    Transaction::encode(principal, method::SPEND, None, payload, &args.encode())
+}
+
+/// Multisig transaction collecting signatures from the keys of the account it's paid by.
+/// Signatures can be added in any order and are verified as they are added.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultisigTransaction {
+    tx: Transaction,
+    genesis_id: GenesisId,
+    keys: SpawnArguments,
+    signatures: BTreeMap<u8, Signature>,
+}
+
+impl MultisigTransaction {
+    /// Collect signatures for `tx` on the network with `genesis_id` from the keys in `keys`, the
+    /// key set of the principal
+    pub fn new(tx: Transaction, genesis_id: GenesisId, keys: SpawnArguments) -> Self {
+        Self {
+            tx,
+            genesis_id,
+            keys,
+            signatures: BTreeMap::new(),
+        }
+    }
+
+    pub fn transaction(&self) -> &Transaction {
+        &self.tx
+    }
+
+    /// Bytes each signer signs
+    pub fn signing_body(&self) -> Vec<u8> {
+        self.tx.signing_body(&self.genesis_id)
+    }
+
+    /// Add the signature of the key at `index`, after checking it against that key
+    pub fn add_signature(
+        &mut self,
+        index: u8,
+        signature: Signature,
+    ) -> Result<(), TransactionError> {
+        let public_key = self.public_key(index)?;
+        if !signature.verify(public_key, &self.signing_body()) {
+            return Err(TransactionError::InvalidSignature(index));
+        }
+        self.signatures.insert(index, signature);
+        Ok(())
+    }
+
+    /// Sign with `signer`, which must hold the key at `index`. To sign with a hardware wallet,
+    /// pass a `RemoteKeypair` for the key's derivation path: the device signature is checked
+    /// against the key before it is added.
+    pub fn sign(&mut self, index: u8, signer: &dyn Signer) -> Result<(), TransactionError> {
+        if signer.try_pubkey()? != *self.public_key(index)? {
+            return Err(TransactionError::SignerKeyMismatch(index));
+        }
+        let signature = signer.try_sign_message(&self.signing_body())?;
+        self.add_signature(index, signature)
+    }
+
+    /// Indices of the keys that have signed so far, in ascending order
+    pub fn signers(&self) -> impl Iterator<Item = u8> + '_ {
+        self.signatures.keys().copied()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.signatures.len() >= self.keys.required as usize
+    }
+
+    /// Append the collected signatures, each preceded by its key index, to produce a transaction
+    /// ready to be submitted. If more signatures than required were collected, those of the
+    /// lowest indices are used.
+    pub fn finalize(&self) -> Result<SignedTransaction, TransactionError> {
+        if !self.is_complete() {
+            return Err(TransactionError::NotEnoughSignatures {
+                required: self.keys.required,
+                collected: self.signatures.len(),
+            });
+        }
+        let mut parts = vec![];
+        for (index, signature) in self.signatures.iter().take(self.keys.required as usize) {
+            scale::encode_compact(&mut parts, (*index).into());
+            parts.extend_from_slice(signature.as_ref());
+        }
+        Ok(SignedTransaction::new(self.tx.clone(), &parts))
+    }
+
+    /// Key at `index` in the key set
+    pub fn public_key(&self, index: u8) -> Result<&Pubkey, TransactionError> {
+        self.keys
+            .public_keys
+            .get(index as usize)
+            .ok_or(TransactionError::InvalidSignerIndex {
+                index,
+                keys: self.keys.public_keys.len(),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        spacemesh_derivation_path::DerivationPath,
+        spacemesh_signer::{SoftwareKeypair, SIGNATURE_BYTES},
+    };
+
+    const GENESIS_ID: GenesisId = [3u8; 20];
+
+    fn keypairs() -> Vec<SoftwareKeypair> {
+        (0..3)
+            .map(|account| {
+                SoftwareKeypair::from_seed(
+                    &[4u8; 64],
+                    DerivationPath::new_bip44(Some(account), None),
+                )
+                .unwrap()
+            })
+            .collect()
+    }
+
+    fn key_set(keypairs: &[SoftwareKeypair], required: u8) -> SpawnArguments {
+        SpawnArguments::new(required, keypairs.iter().map(|k| k.pubkey()).collect()).unwrap()
+    }
+
+    #[test]
+    fn test_spawn_arguments() {
+        let keypairs = keypairs();
+        let keys = key_set(&keypairs, 2);
+        assert_eq!(keys.encode()[..2], [0x08, 0x0c]);
+        assert_eq!(keys.encode().len(), 2 + 3 * 32);
+
+        assert_ne!(keys.principal(), key_set(&keypairs, 3).principal());
+        assert_ne!(keys.principal(), key_set(&keypairs[..2], 2).principal());
+        assert_ne!(
+            keys.principal(),
+            crate::wallet::principal(&keypairs[0].pubkey())
+        );
+
+        let tx = self_spawn(&keys, &Payload::default());
+        assert_eq!(&tx.as_bytes()[1..25], keys.principal().as_ref());
+        assert_eq!(&tx.as_bytes()[26..50], TEMPLATE_ADDRESS.as_ref());
+
+        assert!(SpawnArguments::new(0, vec![Pubkey::new_unique()]).is_err());
+        assert!(SpawnArguments::new(2, vec![Pubkey::new_unique()]).is_err());
+        assert!(SpawnArguments::new(1, vec![]).is_err());
+        assert!(SpawnArguments::new(1, vec![Pubkey::new_unique(); MAX_KEYS + 1]).is_err());
+    }
+
+    #[test]
+    fn test_collect_signatures() {
+        let keypairs = keypairs();
+        let keys = key_set(&keypairs, 2);
+        let tx = spend(
+            &keys.principal(),
+            &SpendArguments {
+                destination: crate::wallet::principal(&Pubkey::new_unique()),
+                amount: 1000,
+            },
+            &Payload {
+                nonce: 1,
+                gas_price: 1,
+            },
+        );
+        let mut multisig = MultisigTransaction::new(tx.clone(), GENESIS_ID, keys);
+
+        assert_eq!(
+            multisig.finalize(),
+            Err(TransactionError::NotEnoughSignatures {
+                required: 2,
+                collected: 0
+            })
+        );
+        assert_eq!(
+            multisig.sign(0, &keypairs[1]),
+            Err(TransactionError::SignerKeyMismatch(0))
+        );
+        assert_eq!(
+            multisig.add_signature(0, keypairs[1].sign_message(&multisig.signing_body())),
+            Err(TransactionError::InvalidSignature(0))
+        );
+        assert_eq!(
+            multisig.add_signature(5, Signature::default()),
+            Err(TransactionError::InvalidSignerIndex { index: 5, keys: 3 })
+        );
+
+        // signatures arrive out of order
+        multisig.sign(2, &keypairs[2]).unwrap();
+        assert!(!multisig.is_complete());
+        let signature = keypairs[0].sign_message(&multisig.signing_body());
+        multisig.add_signature(0, signature).unwrap();
+        assert!(multisig.is_complete());
+        assert_eq!(multisig.signers().collect::<Vec<_>>(), vec![0, 2]);
+
+        let signed = multisig.finalize().unwrap();
+        let (body, parts) = signed.as_bytes().split_at(tx.as_bytes().len());
+        assert_eq!(body, tx.as_bytes());
+        assert_eq!(parts.len(), 2 * (1 + SIGNATURE_BYTES));
+        assert_eq!(parts[0], 0x00);
+        assert_eq!(&parts[1..65], signature.as_ref());
+        assert_eq!(parts[65], 0x08);
+    }
+}
@@ -0,0 +1,54 @@
+//! The subset of SCALE encoding used by transactions. Integers are compact-encoded and fixed-size
+//! byte arrays are written as is.
+
+const SINGLE_BYTE_MAX: u64 = (1 << 6) - 1;
+const TWO_BYTE_MAX: u64 = (1 << 14) - 1;
+const FOUR_BYTE_MAX: u64 = (1 << 30) - 1;
+
+const MODE_SINGLE_BYTE: u8 = 0b00;
+const MODE_TWO_BYTE: u8 = 0b01;
+const MODE_FOUR_BYTE: u8 = 0b10;
+const MODE_BIG_INTEGER: u8 = 0b11;
+
+/// Append the compact encoding of `value` to `out`
+pub(crate) fn encode_compact(out: &mut Vec<u8>, value: u64) {
+    if value <= SINGLE_BYTE_MAX {
+        out.push(((value as u8) << 2) | MODE_SINGLE_BYTE);
+    } else if value <= TWO_BYTE_MAX {
+        out.extend_from_slice(&(((value as u16) << 2) | MODE_TWO_BYTE as u16).to_le_bytes());
+    } else if value <= FOUR_BYTE_MAX {
+        out.extend_from_slice(&(((value as u32) << 2) | MODE_FOUR_BYTE as u32).to_le_bytes());
+    } else {
+        let len = 8 - value.leading_zeros() as usize / 8;
+        out.push((((len - 4) as u8) << 2) | MODE_BIG_INTEGER);
+        out.extend_from_slice(&value.to_le_bytes()[..len]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compact() {
+        let cases: &[(u64, &[u8])] = &[
+            (0, &[0x00]),
+            (1, &[0x04]),
+            (63, &[0xfc]),
+            (64, &[0x01, 0x01]),
+            (16383, &[0xfd, 0xff]),
+            (16384, &[0x02, 0x00, 0x01, 0x00]),
+            (1073741823, &[0xfe, 0xff, 0xff, 0xff]),
+            (1073741824, &[0x03, 0x00, 0x00, 0x00, 0x40]),
+            (
+                u64::MAX,
+                &[0x13, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+            ),
+        ];
+        for (value, expected) in cases {
+            let mut out = vec![];
+            encode_compact(&mut out, *value);
+            assert_eq!(out, *expected, "encoding {value}");
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use {
+    crate::{scale, Address},
+    spacemesh_signer::{Signature, Signer, SignerError},
+};
+
+/// Number of bytes in a genesis ID
+pub const GENESIS_ID_BYTES: usize = 20;
+
+/// ID of the network genesis, which every transaction signature commits to
+pub type GenesisId = [u8; GENESIS_ID_BYTES];
+
+/// Transaction encoding version
+const TX_VERSION: u64 = 0;
+
+/// Template method selectors
+pub(crate) mod method {
+    pub(crate) const SPAWN: u64 = 0;
+    pub(crate) const SPEND: u64 = 16;
+}
+
+/// Nonce and gas price of a transaction, paid by its principal
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Payload {
+    pub nonce: u64,
+    pub gas_price: u64,
+}
+
+impl Payload {
+    fn encode(&self, out: &mut Vec<u8>) {
+        scale::encode_compact(out, self.nonce);
+        scale::encode_compact(out, self.gas_price);
+    }
+}
+
+/// Unsigned transaction, in its canonical SCALE encoding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transaction(Vec<u8>);
+
+impl Transaction {
+    /// Encode a transaction calling `method` on the account at `principal`. Spawn transactions
+    /// name the template of the account they spawn.
+    pub(crate) fn encode(
+        principal: &Address,
+        method: u64,
+        template: Option<&Address>,
+        payload: &Payload,
+        arguments: &[u8],
+    ) -> Self {
+        let mut out = vec![];
+        scale::encode_compact(&mut out, TX_VERSION);
+        out.extend_from_slice(principal.as_ref());
+        scale::encode_compact(&mut out, method);
+        if let Some(template) = template {
+            out.extend_from_slice(template.as_ref());
+        }
+        payload.encode(&mut out);
+        out.extend_from_slice(arguments);
+        Self(out)
+    }
+
+    /// Wrap an already encoded transaction
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Bytes a signature of this transaction covers on the network with `genesis_id`
+    pub fn signing_body(&self, genesis_id: &GenesisId) -> Vec<u8> {
+        signing_body(genesis_id, &self.0)
+    }
+
+    /// Sign the transaction with `signer`
+    pub fn sign(
+        &self,
+        signer: &dyn Signer,
+        genesis_id: &GenesisId,
+    ) -> Result<SignedTransaction, SignerError> {
+        let signature = signer.try_sign_message(&self.signing_body(genesis_id))?;
+        Ok(self.clone().with_signature(&signature))
+    }
+
+    /// Attach a signature made over `signing_body`
+    pub fn with_signature(self, signature: &Signature) -> SignedTransaction {
+        SignedTransaction::new(self, signature.as_ref())
+    }
+}
+
+/// Transaction followed by its signature, ready to be submitted to a node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedTransaction(Vec<u8>);
+
+impl SignedTransaction {
+    /// Append the encoded `signatures` to `tx`
+    pub(crate) fn new(tx: Transaction, signatures: &[u8]) -> Self {
+        let mut bytes = tx.0;
+        bytes.extend_from_slice(signatures);
+        Self(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+/// Bytes a signature of the encoded transaction `tx` covers on the network with `genesis_id`
+pub fn signing_body(genesis_id: &GenesisId, tx: &[u8]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(GENESIS_ID_BYTES + tx.len());
+    body.extend_from_slice(genesis_id);
+    body.extend_from_slice(tx);
+    body
+}
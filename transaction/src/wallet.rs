@@ -0,0 +1,157 @@
+//! Transactions for the single-signature wallet template
+
+use {
+    crate::{
+        scale,
+        transaction::{method, Payload, Transaction},
+        Address, ADDRESS_BYTES,
+    },
+    spacemesh_signer::Pubkey,
+};
+
+/// Address of the wallet template
+pub const TEMPLATE_ADDRESS: Address = Address::new_from_array({
+    let mut bytes = [0u8; ADDRESS_BYTES];
+    bytes[ADDRESS_BYTES - 1] = 1;
+    bytes
+});
+
+/// Arguments of the spawn method: the key that controls the account
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpawnArguments {
+    pub public_key: Pubkey,
+}
+
+impl SpawnArguments {
+    fn encode(&self) -> Vec<u8> {
+        self.public_key.as_ref().to_vec()
+    }
+}
+
+/// Arguments of the spend method
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpendArguments {
+    pub destination: Address,
+    pub amount: u64,
+}
+
+impl SpendArguments {
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut out = self.destination.as_ref().to_vec();
+        scale::encode_compact(&mut out, self.amount);
+        out
+    }
+}
+
+/// Address of the wallet account controlled by `public_key`
+pub fn principal(public_key: &Pubkey) -> Address {
+    let args = SpawnArguments {
+        public_key: *public_key,
+    };
+    crate::compute_principal(&TEMPLATE_ADDRESS, &args.encode())
+}
+
+/// Spawn the wallet account controlled by `args.public_key`, paid by the account itself. The
+/// account must have been funded first.
+pub fn self_spawn(args: &SpawnArguments, payload: &Payload) -> Transaction {
+    spawn(&principal(&args.public_key), args, payload)
+}
+
+/// Spawn the wallet account controlled by `args.public_key`, paid by `principal`
+pub fn spawn(principal: &Address, args: &SpawnArguments, payload: &Payload) -> Transaction {
+    Transaction::encode(
+        principal,
+        method::SPAWN,
+        Some(&TEMPLATE_ADDRESS),
+        payload,
+        &args.encode(),
+    )
+}
+
+/// Transfer `args.amount` from the wallet account at `principal` to `args.destination`
+pub fn spend(principal: &Address, args: &SpendArguments, payload: &Payload) -> Transaction {
+    Transaction::encode(principal, method::SPEND, None, payload, &args.encode())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::GenesisId,
+        spacemesh_derivation_path::DerivationPath,
+        spacemesh_signer::{Signer, SoftwareKeypair, SIGNATURE_BYTES},
+    };
+
+    const GENESIS_ID: GenesisId = [3u8; 20];
+
+    #[test]
+    fn test_spend_encoding() {
+        let principal = Address::from_hash(&[1u8; 32]);
+        let destination = Address::from_hash(&[2u8; 32]);
+        let tx = spend(
+            &principal,
+            &SpendArguments {
+                destination,
+                amount: 100,
+            },
+            &Payload {
+                nonce: 1,
+                gas_price: 64,
+            },
+        );
+
+        let mut expected = vec![0x00];
+        expected.extend_from_slice(principal.as_ref());
+        expected.push(0x40);
+        expected.extend_from_slice(&[0x04, 0x01, 0x01]);
+        expected.extend_from_slice(destination.as_ref());
+        expected.extend_from_slice(&[0x91, 0x01]);
+        assert_eq!(tx.as_bytes(), expected);
+    }
+
+    #[test]
+    fn test_self_spawn() {
+        let public_key = Pubkey::new_from_array([5u8; 32]);
+        let args = SpawnArguments { public_key };
+        let payload = Payload {
+            nonce: 0,
+            gas_price: 1,
+        };
+        let tx = self_spawn(&args, &payload);
+        assert_eq!(tx, spawn(&principal(&public_key), &args, &payload));
+
+        let bytes = tx.as_bytes();
+        assert_eq!(&bytes[1..25], principal(&public_key).as_ref());
+        assert_eq!(bytes[25], 0x00);
+        assert_eq!(&bytes[26..50], TEMPLATE_ADDRESS.as_ref());
+        assert_eq!(&bytes[50..52], &[0x00, 0x04]);
+        assert_eq!(&bytes[52..], public_key.as_ref());
+
+        assert_ne!(principal(&public_key), principal(&Pubkey::new_unique()));
+    }
+
+    #[test]
+    fn test_sign() {
+        let keypair = SoftwareKeypair::from_seed(
+            &[9u8; 64],
+            DerivationPath::from_absolute_path_str("m/44'/540'/0'/0'/0'").unwrap(),
+        )
+        .unwrap();
+        let tx = self_spawn(
+            &SpawnArguments {
+                public_key: keypair.pubkey(),
+            },
+            &Payload::default(),
+        );
+
+        let signed = tx.sign(&keypair, &GENESIS_ID).unwrap();
+        let (body, signature) = signed.as_bytes().split_at(tx.as_bytes().len());
+        assert_eq!(body, tx.as_bytes());
+        assert_eq!(signature.len(), SIGNATURE_BYTES);
+        assert_eq!(
+            signature,
+            keypair.sign_message(&tx.signing_body(&GENESIS_ID)).as_ref()
+        );
+        assert_eq!(&tx.signing_body(&GENESIS_ID)[..20], &GENESIS_ID);
+    }
+}
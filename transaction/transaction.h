@@ -0,0 +1,112 @@
+/* Warning, this file is autogenerated by cbindgen. Don't modify this manually. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+
+/**
+ * Number of bytes in an address
+ */
+#define ADDRESS_BYTES 24
+
+/**
+ * Number of bytes in a genesis ID
+ */
+#define GENESIS_ID_BYTES 20
+
+/**
+ * Largest number of keys in a multisig account
+ */
+#define MAX_KEYS 10
+
+/**
+ * attach_signature_c writes the unsigned transaction tx followed by the 64-byte signature to
+ * result, producing a transaction ready to be submitted. result_cap is the size of result; the
+ * length of the signed transaction is written to result_len. It returns a status code, with a
+ * return value of zero indicating success.
+ */
+uint16_t attach_signature_c(const uint8_t *tx,
+                            size_t tx_len,
+                            const uint8_t *signature,
+                            uint8_t *result,
+                            size_t result_cap,
+                            size_t *result_len);
+
+/**
+ * decode_address_c parses the NUL-terminated bech32 address and writes its 24 bytes to result,
+ * which must be at least 24 bytes long. It returns a status code, with a return value of zero
+ * indicating success.
+ */
+uint16_t decode_address_c(const char *address, uint8_t *result);
+
+/**
+ * encode_address_c writes the 24-byte address, rendered as bech32 with human-readable part hrp,
+ * to result as a NUL-terminated string. result_cap is the size of result. It returns a status
+ * code, with a return value of zero indicating success.
+ */
+uint16_t encode_address_c(const uint8_t *address, const char *hrp, char *result, size_t result_cap);
+
+/**
+ * signing_body_c writes the bytes to sign for the unsigned transaction tx on the network with
+ * the 20-byte genesis_id to result. result_cap is the size of result; the length of the signing
+ * body is written to result_len. It returns a status code, with a return value of zero
+ * indicating success.
+ */
+uint16_t signing_body_c(const uint8_t *genesis_id,
+                        const uint8_t *tx,
+                        size_t tx_len,
+                        uint8_t *result,
+                        size_t result_cap,
+                        size_t *result_len);
+
+/**
+ * wallet_principal_c writes the 24-byte address of the wallet account controlled by the 32-byte
+ * public_key to result, which must be at least 24 bytes long. It returns a status code, with a
+ * return value of zero indicating success.
+ */
+uint16_t wallet_principal_c(const uint8_t *public_key, uint8_t *result);
+
+/**
+ * wallet_self_spawn_c writes the unsigned transaction spawning the wallet account controlled by
+ * the 32-byte public_key, paid by the account itself, to result. result_cap is the size of
+ * result; the length of the transaction is written to result_len. It returns a status code, with
+ * a return value of zero indicating success.
+ */
+uint16_t wallet_self_spawn_c(const uint8_t *public_key,
+                             uint64_t nonce,
+                             uint64_t gas_price,
+                             uint8_t *result,
+                             size_t result_cap,
+                             size_t *result_len);
+
+/**
+ * wallet_spawn_c writes the unsigned transaction spawning the wallet account controlled by the
+ * 32-byte public_key, paid by the 24-byte principal, to result. result_cap is the size of
+ * result; the length of the transaction is written to result_len. It returns a status code, with
+ * a return value of zero indicating success.
+ */
+uint16_t wallet_spawn_c(const uint8_t *principal,
+                        const uint8_t *public_key,
+                        uint64_t nonce,
+                        uint64_t gas_price,
+                        uint8_t *result,
+                        size_t result_cap,
+                        size_t *result_len);
+
+/**
+ * wallet_spend_c writes the unsigned transaction transferring amount from the wallet account at
+ * the 24-byte principal to the 24-byte destination to result. result_cap is the size of result;
+ * the length of the transaction is written to result_len. It returns a status code, with a
+ * return value of zero indicating success.
+ */
+uint16_t wallet_spend_c(const uint8_t *principal,
+                        const uint8_t *destination,
+                        uint64_t amount,
+                        uint64_t nonce,
+                        uint64_t gas_price,
+                        uint8_t *result,
+                        size_t result_cap,
+                        size_t *result_len);